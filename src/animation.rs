@@ -0,0 +1,233 @@
+use crate::text_format::{data_lines, invalid_line, parse_floats};
+use nalgebra_glm::Vec3;
+use std::fs;
+use std::io;
+
+// Propiedad animable de la escena, indexada por objeto o luz
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Target {
+    ObjectPosition(usize),
+    ObjectSize(usize), // (ancho, alto, profundidad) o radio en x
+    MaterialColor(usize),
+    MaterialEmission(usize),
+    MaterialTransparency(usize),
+    LightPosition(usize),
+    LightColor(usize),
+    LightIntensity(usize),
+}
+
+impl Target {
+    // Nombre usado en los archivos de línea de tiempo
+    fn from_name(name: &str, index: usize) -> Option<Target> {
+        Some(match name {
+            "object_position" => Target::ObjectPosition(index),
+            "object_size" => Target::ObjectSize(index),
+            "material_color" => Target::MaterialColor(index),
+            "material_emission" => Target::MaterialEmission(index),
+            "material_transparency" => Target::MaterialTransparency(index),
+            "light_position" => Target::LightPosition(index),
+            "light_color" => Target::LightColor(index),
+            "light_intensity" => Target::LightIntensity(index),
+            _ => return None,
+        })
+    }
+
+    // Índice del objeto animado; las luces no dependen de los objetos
+    fn object_index(&self) -> Option<usize> {
+        match *self {
+            Target::ObjectPosition(index)
+            | Target::ObjectSize(index)
+            | Target::MaterialColor(index)
+            | Target::MaterialEmission(index)
+            | Target::MaterialTransparency(index) => Some(index),
+            Target::LightPosition(_) | Target::LightColor(_) | Target::LightIntensity(_) => None,
+        }
+    }
+
+    fn with_object_index(self, index: usize) -> Self {
+        match self {
+            Target::ObjectPosition(_) => Target::ObjectPosition(index),
+            Target::ObjectSize(_) => Target::ObjectSize(index),
+            Target::MaterialColor(_) => Target::MaterialColor(index),
+            Target::MaterialEmission(_) => Target::MaterialEmission(index),
+            Target::MaterialTransparency(_) => Target::MaterialTransparency(index),
+            light => light,
+        }
+    }
+}
+
+// Origen del valor: keyframes interpolados o una expresión en función del tiempo.
+// Los valores escalares usan la componente x.
+pub enum Driver {
+    Keyframes(Vec<(f32, Vec3)>),
+    Expression(Box<dyn Fn(f32) -> Vec3 + Send + Sync>),
+}
+
+pub struct Track {
+    pub target: Target,
+    pub driver: Driver,
+}
+
+impl Track {
+    pub fn keyframes(target: Target, mut keys: Vec<(f32, Vec3)>) -> Self {
+        keys.sort_by(|a, b| a.0.total_cmp(&b.0));
+        Track {
+            target,
+            driver: Driver::Keyframes(keys),
+        }
+    }
+
+    pub fn expression(
+        target: Target,
+        expression: impl Fn(f32) -> Vec3 + Send + Sync + 'static,
+    ) -> Self {
+        Track {
+            target,
+            driver: Driver::Expression(Box::new(expression)),
+        }
+    }
+
+    // Valor en `time`; los keyframes se interpolan linealmente y se sostienen en los extremos
+    pub fn value(&self, time: f32) -> Option<Vec3> {
+        match &self.driver {
+            Driver::Expression(expression) => Some(expression(time)),
+            Driver::Keyframes(keys) => {
+                let first = keys.first()?;
+                let last = keys.last()?;
+                if time <= first.0 {
+                    return Some(first.1);
+                }
+                if time >= last.0 {
+                    return Some(last.1);
+                }
+                let segment = keys.windows(2).find(|pair| time < pair[1].0)?;
+                let (t0, v0) = segment[0];
+                let (t1, v1) = segment[1];
+                let t = if t1 > t0 {
+                    (time - t0) / (t1 - t0)
+                } else {
+                    0.0
+                };
+                Some(v0 + (v1 - v0) * t)
+            }
+        }
+    }
+
+    // Último instante con keyframe; las expresiones no tienen fin
+    pub fn duration(&self) -> f32 {
+        match &self.driver {
+            Driver::Keyframes(keys) => keys.last().map_or(0.0, |key| key.0),
+            Driver::Expression(_) => 0.0,
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct Timeline {
+    pub tracks: Vec<Track>,
+}
+
+impl Timeline {
+    pub fn add(&mut self, track: Track) {
+        self.tracks.push(track);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tracks.is_empty()
+    }
+
+    // Formato de texto, una línea por keyframe o expresión:
+    // `destino índice key tiempo valor...` agrega un keyframe a la pista del destino y
+    // `destino índice sin frecuencia base... amplitud...` la define como
+    // base + amplitud · sin(frecuencia · t). Los valores son 3 (vector) o 1 (escalar).
+    pub fn load(file_path: &str) -> io::Result<Self> {
+        let mut keyframes: Vec<(Target, Vec<(f32, Vec3)>)> = Vec::new();
+        let mut timeline = Timeline::default();
+
+        for (number, line) in data_lines(&fs::read_to_string(file_path)?) {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 4 {
+                return Err(invalid_line(
+                    number,
+                    "se esperaba destino, índice, tipo y valores",
+                ));
+            }
+            let index: usize = fields[1]
+                .parse()
+                .map_err(|_| invalid_line(number, "índice inválido"))?;
+            let target = Target::from_name(fields[0], index).ok_or_else(|| {
+                invalid_line(number, &format!("destino desconocido: {}", fields[0]))
+            })?;
+            let values = parse_floats(number, &fields[3..])?;
+
+            match fields[2] {
+                "key" => {
+                    let value = vector(&values[1..]).ok_or_else(|| {
+                        invalid_line(number, "se esperaba tiempo y 1 o 3 valores")
+                    })?;
+                    match keyframes
+                        .iter_mut()
+                        .find(|(existing, _)| *existing == target)
+                    {
+                        Some((_, keys)) => keys.push((values[0], value)),
+                        None => keyframes.push((target, vec![(values[0], value)])),
+                    }
+                }
+                "sin" => {
+                    let half = (values.len() - 1) / 2;
+                    let (base, amplitude) =
+                        match (vector(&values[1..1 + half]), vector(&values[1 + half..])) {
+                            (Some(base), Some(amplitude)) if values.len() % 2 == 1 => {
+                                (base, amplitude)
+                            }
+                            _ => return Err(invalid_line(
+                                number,
+                                "se esperaba frecuencia, base y amplitud (1 o 3 valores cada una)",
+                            )),
+                        };
+                    let frequency = values[0];
+                    timeline.add(Track::expression(target, move |t| {
+                        base + amplitude * (t * frequency).sin()
+                    }));
+                }
+                kind => {
+                    return Err(invalid_line(
+                        number,
+                        &format!("tipo desconocido: {} (usa key o sin)", kind),
+                    ))
+                }
+            }
+        }
+
+        for (target, keys) in keyframes {
+            timeline.add(Track::keyframes(target, keys));
+        }
+        Ok(timeline)
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.tracks.iter().map(Track::duration).fold(0.0, f32::max)
+    }
+
+    // Ajusta los índices tras quitar el objeto `removed` de la escena; sus pistas se descartan
+    pub fn remove_object(&mut self, removed: usize) {
+        self.tracks
+            .retain(|track| track.target.object_index() != Some(removed));
+        for track in &mut self.tracks {
+            if let Some(index) = track.target.object_index() {
+                if index > removed {
+                    track.target = track.target.with_object_index(index - 1);
+                }
+            }
+        }
+    }
+}
+
+// Un escalar se repite en las tres componentes
+fn vector(values: &[f32]) -> Option<Vec3> {
+    match values {
+        [value] => Some(Vec3::repeat(*value)),
+        [x, y, z] => Some(Vec3::new(*x, *y, *z)),
+        _ => None,
+    }
+}
@@ -0,0 +1,156 @@
+// Mediciones de rendimiento sobre escenas de referencia con cámaras fijas, para comparar
+// el render entre cambios
+use crate::camera::Camera;
+use crate::denoise::{DenoiseFilter, Denoiser};
+use crate::framebuffer::Framebuffer;
+use crate::render_mode::RenderMode;
+use crate::renderer::{Quality, RayStats, RenderContext};
+use crate::scene::{self, Scene};
+use nalgebra_glm::Vec3;
+use std::time::{Duration, Instant};
+
+// Resolución fija para que los resultados sean comparables entre máquinas y cambios
+const BENCH_WIDTH: usize = 400;
+const BENCH_HEIGHT: usize = 300;
+const BENCH_SUN_ANGLE: f32 = 1.0;
+const VOXEL_FIELD_SIZE: usize = 32;
+// Todavía no hay BVH: cada rayo prueba todos los objetos, así que tests_per_ray es el
+// número de objetos y sirve de referencia para comparar una futura estructura de aceleración
+const ACCELERATION: &str = "linear";
+
+struct Reference {
+    name: &'static str,
+    build: fn() -> Scene,
+    eye: Vec3,
+    center: Vec3,
+}
+
+fn references() -> Vec<Reference> {
+    vec![
+        Reference {
+            name: "diorama",
+            build: scene::diorama,
+            eye: Vec3::new(0.0, 3.0, 7.0),
+            center: Vec3::new(0.0, 0.5, 0.0),
+        },
+        Reference {
+            name: "voxel_field",
+            build: || scene::voxel_field(VOXEL_FIELD_SIZE),
+            eye: Vec3::new(0.0, 8.0, 18.0),
+            center: Vec3::zeros(),
+        },
+        Reference {
+            name: "glass",
+            build: scene::glass_gallery,
+            eye: Vec3::new(0.0, 3.0, 6.0),
+            center: Vec3::zeros(),
+        },
+    ]
+}
+
+pub struct BenchResult {
+    pub scene: &'static str,
+    pub objects: usize,
+    pub setup: Duration, // Construir la escena (incluye cargar texturas la primera vez)
+    pub render: Duration, // Imagen final a calidad completa
+    pub features: Duration, // Albedo, normales y profundidad para el filtro de ruido
+    pub denoise: Duration,
+    pub stats: RayStats, // Del render de la imagen final
+}
+
+impl BenchResult {
+    pub fn rays_per_second(&self) -> f64 {
+        let seconds = self.render.as_secs_f64();
+        if seconds > 0.0 {
+            self.stats.total() as f64 / seconds
+        } else {
+            0.0
+        }
+    }
+
+    // Una línea JSON por escena (formato JSON Lines)
+    pub fn to_json(&self) -> String {
+        let stats = &self.stats;
+        let rays = stats.total().max(1) as f64;
+        let tests = stats.intersection_tests.max(1) as f64;
+        let milliseconds = |duration: Duration| duration.as_secs_f64() * 1000.0;
+        format!(
+            concat!(
+                "{{\"scene\":\"{}\",\"width\":{},\"height\":{},\"objects\":{},",
+                "\"timings_ms\":{{\"setup\":{:.3},\"render\":{:.3},\"features\":{:.3},\"denoise\":{:.3}}},",
+                "\"rays\":{{\"primary\":{},\"shadow\":{},\"secondary\":{},\"total\":{},\"max_depth\":{}}},",
+                "\"rays_per_second\":{:.0},",
+                "\"intersections\":{{\"acceleration\":\"{}\",\"tests\":{},\"hits\":{},\"tests_per_ray\":{:.2},\"hit_rate\":{:.4}}}}}"
+            ),
+            self.scene,
+            BENCH_WIDTH,
+            BENCH_HEIGHT,
+            self.objects,
+            milliseconds(self.setup),
+            milliseconds(self.render),
+            milliseconds(self.features),
+            milliseconds(self.denoise),
+            stats.primary,
+            stats.shadow,
+            stats.secondary,
+            stats.total(),
+            stats.max_depth,
+            self.rays_per_second(),
+            ACCELERATION,
+            stats.intersection_tests,
+            stats.intersection_hits,
+            stats.intersection_tests as f64 / rays,
+            stats.intersection_hits as f64 / tests,
+        )
+    }
+}
+
+// Renderiza cada escena de referencia con una cámara estenopeica (sin ruido de lente)
+pub fn run() -> Vec<BenchResult> {
+    references().iter().map(bench_scene).collect()
+}
+
+fn bench_scene(reference: &Reference) -> BenchResult {
+    let setup_start = Instant::now();
+    let mut scene = (reference.build)();
+    scene.set_sun_angle(BENCH_SUN_ANGLE);
+    scene.animate(0.0);
+    let setup = setup_start.elapsed();
+
+    let mut camera = Camera::new(reference.eye, reference.center, Vec3::new(0.0, 1.0, 0.0));
+    camera.aspect_ratio = BENCH_WIDTH as f32 / BENCH_HEIGHT as f32;
+    let context = RenderContext {
+        scene: &scene,
+        camera: &camera,
+        width: BENCH_WIDTH,
+        height: BENCH_HEIGHT,
+        time: 0.0,
+        quality: Quality::FULL,
+        mode: RenderMode::Beauty,
+        motion: None,
+        placements: &[],
+    };
+    let mut framebuffer = Framebuffer::new(BENCH_WIDTH, BENCH_HEIGHT);
+
+    let render_start = Instant::now();
+    let stats = context.render(&mut framebuffer);
+    let render = render_start.elapsed();
+
+    let features_start = Instant::now();
+    let features = context.render_features();
+    let features_time = features_start.elapsed();
+
+    let denoise_start = Instant::now();
+    Denoiser::new(DenoiseFilter::ATrous).apply(&mut framebuffer, &features);
+    let denoise = denoise_start.elapsed();
+
+    BenchResult {
+        scene: reference.name,
+        objects: scene.objects.len(),
+        setup,
+        render,
+        features: features_time,
+        denoise,
+        stats,
+    }
+}
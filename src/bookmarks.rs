@@ -0,0 +1,119 @@
+use crate::camera::Camera;
+use crate::text_format::{data_lines, invalid_line, parse_floats};
+use nalgebra_glm::Vec3;
+use std::fs;
+use std::io;
+
+pub const BOOKMARK_SLOTS: usize = 10;
+
+// Vista guardada: cámara, campo de visión y ángulo del sol
+#[derive(Debug, Clone)]
+pub struct Bookmark {
+    pub name: String,
+    pub eye: Vec3,
+    pub center: Vec3,
+    pub fov: f32,
+    pub sun_angle: f32,
+}
+
+impl Bookmark {
+    pub fn from_camera(name: &str, camera: &Camera, sun_angle: f32) -> Self {
+        Bookmark {
+            name: name.to_string(),
+            eye: camera.eye,
+            center: camera.center,
+            fov: camera.fov,
+            sun_angle,
+        }
+    }
+
+    // Restaura la cámara y devuelve el ángulo del sol guardado
+    pub fn apply(&self, camera: &mut Camera) -> f32 {
+        camera.eye = self.eye;
+        camera.center = self.center;
+        camera.set_fov(self.fov);
+        self.sun_angle
+    }
+}
+
+// Resto de la línea después de los primeros `count` campos separados por espacios, tal
+// cual (el nombre de un marcador puede tener espacios)
+fn tail_after_fields(line: &str, count: usize) -> &str {
+    let mut rest = line.trim_start();
+    for _ in 0..count {
+        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        rest = rest[end..].trim_start();
+    }
+    rest.trim_end()
+}
+
+// Marcadores indexados por las teclas 0-9
+#[derive(Debug, Clone, Default)]
+pub struct Bookmarks {
+    pub slots: [Option<Bookmark>; BOOKMARK_SLOTS],
+}
+
+impl Bookmarks {
+    pub fn get(&self, slot: usize) -> Option<&Bookmark> {
+        self.slots.get(slot)?.as_ref()
+    }
+
+    pub fn set(&mut self, slot: usize, bookmark: Bookmark) {
+        if slot < BOOKMARK_SLOTS {
+            self.slots[slot] = Some(bookmark);
+        }
+    }
+
+    // Formato de texto: una línea por marcador con
+    // `tecla ojo.x ojo.y ojo.z centro.x centro.y centro.z fov ángulo_sol nombre`
+    pub fn load(file_path: &str) -> io::Result<Self> {
+        let mut bookmarks = Bookmarks::default();
+        for (number, line) in data_lines(&fs::read_to_string(file_path)?) {
+            let fields: Vec<&str> = line.split_whitespace().take(9).collect();
+            if fields.len() < 9 {
+                return Err(invalid_line(number, "se esperaban al menos 9 valores"));
+            }
+            let slot: usize = fields[0]
+                .parse()
+                .map_err(|_| invalid_line(number, "tecla inválida"))?;
+            let values = parse_floats(number, &fields[1..9])?;
+            let name = tail_after_fields(line, 9);
+
+            bookmarks.set(
+                slot,
+                Bookmark {
+                    name: name.to_string(),
+                    eye: Vec3::new(values[0], values[1], values[2]),
+                    center: Vec3::new(values[3], values[4], values[5]),
+                    fov: values[6],
+                    sun_angle: values[7],
+                },
+            );
+        }
+        Ok(bookmarks)
+    }
+
+    pub fn save(&self, file_path: &str) -> io::Result<()> {
+        let mut contents = String::from(
+            "# tecla ojo.x ojo.y ojo.z centro.x centro.y centro.z fov ángulo_sol nombre\n",
+        );
+        for (slot, bookmark) in self.slots.iter().enumerate() {
+            if let Some(b) = bookmark {
+                contents.push_str(&format!(
+                    "{} {} {} {} {} {} {} {} {} {}\n",
+                    slot,
+                    b.eye.x,
+                    b.eye.y,
+                    b.eye.z,
+                    b.center.x,
+                    b.center.y,
+                    b.center.z,
+                    b.fov,
+                    b.sun_angle,
+                    b.name
+                ));
+            }
+        }
+        fs::write(file_path, contents)
+    }
+}
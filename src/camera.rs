@@ -1,15 +1,222 @@
 extern crate nalgebra_glm;
 use nalgebra_glm::Vec3;
+use std::f32::consts::PI;
 
+// Límites del campo de visión vertical, en grados
+const MIN_FOV: f32 = 10.0;
+const MAX_FOV: f32 = 150.0;
+
+// Forma de la apertura del lente, define cómo se ven los puntos desenfocados
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Bokeh {
+    Circle,
+    Hexagon,
+}
+
+impl Bokeh {
+    // Convierte (u, v) uniformes en [0, 1) a un punto de la apertura de radio 1
+    pub fn sample(&self, u: f32, v: f32) -> (f32, f32) {
+        match self {
+            Bokeh::Circle => {
+                let r = u.sqrt();
+                let theta = 2.0 * PI * v;
+                (r * theta.cos(), r * theta.sin())
+            }
+            Bokeh::Hexagon => {
+                // Elegir uno de los 6 triángulos y muestrear dentro de él
+                let sector = ((u * 6.0) as usize).min(5);
+                let u = u * 6.0 - sector as f32;
+                let (a, b) = if u + v > 1.0 {
+                    (1.0 - u, 1.0 - v)
+                } else {
+                    (u, v)
+                };
+                let angle0 = sector as f32 * PI / 3.0;
+                let angle1 = angle0 + PI / 3.0;
+                (
+                    a * angle0.cos() + b * angle1.cos(),
+                    a * angle0.sin() + b * angle1.sin(),
+                )
+            }
+        }
+    }
+
+    pub fn next(&self) -> Bokeh {
+        match self {
+            Bokeh::Circle => Bokeh::Hexagon,
+            Bokeh::Hexagon => Bokeh::Circle,
+        }
+    }
+}
+
+// Proyección con la que se generan los rayos primarios
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Projection {
+    Perspective,
+    Orthographic,    // Rayos paralelos, para vistas isométricas
+    Fisheye,         // Equidistante, `fov` es el ángulo cubierto verticalmente
+    Equirectangular, // Panorama de 360° x 180°, ignora `fov` y `aspect_ratio`
+}
+
+impl Projection {
+    pub fn next(&self) -> Projection {
+        match self {
+            Projection::Perspective => Projection::Orthographic,
+            Projection::Orthographic => Projection::Fisheye,
+            Projection::Fisheye => Projection::Equirectangular,
+            Projection::Equirectangular => Projection::Perspective,
+        }
+    }
+}
+
+// Cómo responde la cámara a los controles del visor
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CameraMode {
+    Orbit,   // Gira alrededor de `center`
+    FreeFly, // Primera persona: se mueve libremente y mira con el ratón
+}
+
+#[derive(Clone)]
 pub struct Camera {
-    pub eye: Vec3,    // Posición de la cámara en el espacio del mundo
-    pub center: Vec3, // Punto que la cámara está mirando
-    pub up: Vec3,     // Vector que representa "arriba" para la cámara
+    pub eye: Vec3,           // Posición de la cámara en el espacio del mundo
+    pub center: Vec3,        // Punto que la cámara está mirando
+    pub up: Vec3,            // Vector que representa "arriba" para la cámara
+    pub fov: f32,            // Campo de visión vertical en grados
+    pub near: f32,           // Distancia del plano de recorte cercano
+    pub aspect_ratio: f32,   // Ancho / alto de la imagen
+    pub aperture: f32,       // Radio de la apertura del lente (0 = cámara estenopeica)
+    pub focus_distance: f32, // Distancia al plano enfocado
+    pub bokeh: Bokeh,
+    pub projection: Projection,
 }
 
 impl Camera {
     pub fn new(eye: Vec3, center: Vec3, up: Vec3) -> Self {
-        Self { eye, center, up }
+        Self {
+            eye,
+            center,
+            up,
+            fov: 90.0,
+            near: 0.01,
+            aspect_ratio: 1.0,
+            aperture: 0.0,
+            focus_distance: 5.0,
+            bokeh: Bokeh::Circle,
+            projection: Projection::Perspective,
+        }
+    }
+
+    // Punto de la apertura para las muestras uniformes (u, v)
+    pub fn sample_lens(&self, u: f32, v: f32) -> (f32, f32) {
+        self.bokeh.sample(u, v)
+    }
+
+    pub fn set_fov(&mut self, fov: f32) {
+        self.fov = fov.clamp(MIN_FOV, MAX_FOV);
+    }
+
+    // Genera el rayo (origen, dirección) que pasa por el píxel (x, y).
+    // `offset` es la posición dentro del píxel, (0.5, 0.5) es su centro.
+    // `lens` es el punto de la apertura (ver `sample_lens`), (0, 0) es su centro.
+    pub fn ray_for_pixel(
+        &self,
+        x: usize,
+        y: usize,
+        width: usize,
+        height: usize,
+        offset: (f32, f32),
+        lens: (f32, f32),
+    ) -> (Vec3, Vec3) {
+        // Coordenadas normalizadas del píxel en [-1, 1], con y hacia arriba
+        let ndc_x = 2.0 * (x as f32 + offset.0) / width as f32 - 1.0;
+        let ndc_y = 1.0 - 2.0 * (y as f32 + offset.1) / height as f32;
+
+        match self.projection {
+            Projection::Perspective => self.perspective_ray(ndc_x, ndc_y, lens),
+            Projection::Orthographic => self.orthographic_ray(ndc_x, ndc_y),
+            Projection::Fisheye => self.fisheye_ray(ndc_x, ndc_y),
+            Projection::Equirectangular => self.equirectangular_ray(ndc_x, ndc_y),
+        }
+    }
+
+    fn perspective_ray(&self, ndc_x: f32, ndc_y: f32, lens: (f32, f32)) -> (Vec3, Vec3) {
+        let half_height = (self.fov.to_radians() * 0.5).tan();
+        let half_width = half_height * self.aspect_ratio;
+
+        let screen_x = ndc_x * half_width;
+        let screen_y = ndc_y * half_height;
+
+        let pinhole_direction = self.basis_change(&Vec3::new(screen_x, screen_y, -1.0));
+        let forward = (self.center - self.eye).normalize();
+
+        // Lente delgado: todos los rayos del píxel convergen en el plano enfocado
+        let (lens_point, direction) = if self.aperture > 0.0 {
+            let right = forward.cross(&self.up).normalize();
+            let up = right.cross(&forward).normalize();
+            let focal_point = self.eye
+                + pinhole_direction * (self.focus_distance / pinhole_direction.dot(&forward));
+            let lens_point = self.eye + (right * lens.0 + up * lens.1) * self.aperture;
+            (lens_point, (focal_point - lens_point).normalize())
+        } else {
+            (self.eye, pinhole_direction)
+        };
+
+        // El rayo empieza en el plano cercano, no en el lente
+        let origin = lens_point + direction * (self.near / direction.dot(&forward));
+
+        (origin, direction)
+    }
+
+    fn orthographic_ray(&self, ndc_x: f32, ndc_y: f32) -> (Vec3, Vec3) {
+        // El área visible coincide con la de la perspectiva a la distancia del centro
+        let forward = (self.center - self.eye).normalize();
+        let right = forward.cross(&self.up).normalize();
+        let up = right.cross(&forward).normalize();
+        let half_height =
+            (self.center - self.eye).magnitude() * (self.fov.to_radians() * 0.5).tan();
+        let half_width = half_height * self.aspect_ratio;
+
+        let origin = self.eye
+            + right * (ndc_x * half_width)
+            + up * (ndc_y * half_height)
+            + forward * self.near;
+
+        (origin, forward)
+    }
+
+    fn fisheye_ray(&self, ndc_x: f32, ndc_y: f32) -> (Vec3, Vec3) {
+        let screen_x = ndc_x * self.aspect_ratio;
+        let screen_y = ndc_y;
+        let radius = (screen_x * screen_x + screen_y * screen_y).sqrt();
+
+        // El ángulo respecto al eje de la vista crece linealmente con el radio
+        let theta = radius * self.fov.to_radians() * 0.5;
+        let local = if radius > 0.0 {
+            Vec3::new(
+                theta.sin() * screen_x / radius,
+                theta.sin() * screen_y / radius,
+                -theta.cos(),
+            )
+        } else {
+            Vec3::new(0.0, 0.0, -1.0)
+        };
+
+        let direction = self.basis_change(&local);
+        (self.eye + direction * self.near, direction)
+    }
+
+    fn equirectangular_ray(&self, ndc_x: f32, ndc_y: f32) -> (Vec3, Vec3) {
+        let longitude = ndc_x * PI;
+        let latitude = ndc_y * PI * 0.5;
+
+        let local = Vec3::new(
+            longitude.sin() * latitude.cos(),
+            latitude.sin(),
+            -longitude.cos() * latitude.cos(),
+        );
+
+        let direction = self.basis_change(&local);
+        (self.eye + direction * self.near, direction)
     }
 
     pub fn basis_change(&self, vector: &Vec3) -> Vec3 {
@@ -58,6 +265,45 @@ impl Camera {
         self.eye = new_eye;
     }
 
+    // Enfoca el plano perpendicular a la vista que contiene `point`
+    pub fn focus_at(&mut self, point: &Vec3) {
+        let forward = (self.center - self.eye).normalize();
+        self.focus_distance = (point - self.eye).dot(&forward).max(self.near);
+    }
+
+    // Rotar la vista alrededor del ojo (primera persona), en radianes
+    pub fn look(&mut self, delta_yaw: f32, delta_pitch: f32) {
+        let direction = self.center - self.eye;
+        let distance = direction.magnitude();
+
+        let current_yaw = direction.z.atan2(direction.x);
+        let current_pitch = (direction.y / distance).clamp(-1.0, 1.0).asin();
+
+        let new_yaw = (current_yaw + delta_yaw) % (2.0 * PI);
+        let new_pitch = (current_pitch + delta_pitch).clamp(
+            -std::f32::consts::FRAC_PI_2 + 0.1,
+            std::f32::consts::FRAC_PI_2 - 0.1,
+        );
+
+        self.center = self.eye
+            + Vec3::new(
+                new_yaw.cos() * new_pitch.cos(),
+                new_pitch.sin(),
+                new_yaw.sin() * new_pitch.cos(),
+            ) * distance;
+    }
+
+    // Desplazar ojo y centro juntos; `movement` es (derecha, arriba, adelante)
+    pub fn fly(&mut self, movement: &Vec3) {
+        let forward = (self.center - self.eye).normalize();
+        let right = forward.cross(&self.up).normalize();
+        let up = self.up.normalize();
+
+        let offset = right * movement.x + up * movement.y + forward * movement.z;
+        self.eye += offset;
+        self.center += offset;
+    }
+
     pub fn zoom(&mut self, factor: f32) {
         // Calcular el vector de la dirección desde la cámara hacia el centro
         let direction = (self.center - self.eye).normalize();
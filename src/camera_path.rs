@@ -0,0 +1,188 @@
+use crate::camera::Camera;
+use crate::text_format::{data_lines, invalid_line, parse_floats};
+use nalgebra::UnitQuaternion;
+use nalgebra_glm::Vec3;
+use std::fs;
+use std::io;
+
+// Estado de la cámara en un instante del recorrido
+#[derive(Debug, Clone)]
+pub struct CameraKeyframe {
+    pub time: f32, // Segundos desde el inicio del recorrido
+    pub eye: Vec3,
+    pub center: Vec3,
+    pub fov: f32,
+    pub aperture: f32,
+}
+
+impl CameraKeyframe {
+    pub fn from_camera(camera: &Camera, time: f32) -> Self {
+        CameraKeyframe {
+            time,
+            eye: camera.eye,
+            center: camera.center,
+            fov: camera.fov,
+            aperture: camera.aperture,
+        }
+    }
+
+    fn orientation(&self, up: &Vec3) -> UnitQuaternion<f32> {
+        UnitQuaternion::face_towards(&(self.center - self.eye), up)
+    }
+
+    // FOV, apertura y distancia al objetivo se interpolan juntos como un vector
+    fn lens_parameters(&self) -> Vec3 {
+        Vec3::new(
+            self.fov,
+            self.aperture,
+            (self.center - self.eye).magnitude(),
+        )
+    }
+}
+
+// Recorrido de cámara: posición por Catmull-Rom y orientación por slerp
+#[derive(Debug, Clone, Default)]
+pub struct CameraPath {
+    pub keyframes: Vec<CameraKeyframe>,
+}
+
+impl CameraPath {
+    pub fn new() -> Self {
+        CameraPath {
+            keyframes: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, keyframe: CameraKeyframe) {
+        self.keyframes.push(keyframe);
+        self.keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keyframes.is_empty()
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.keyframes.last().map_or(0.0, |keyframe| keyframe.time)
+    }
+
+    // Estado interpolado en `time`; fuera del recorrido se mantiene el extremo más cercano
+    pub fn sample(&self, time: f32, up: &Vec3) -> Option<CameraKeyframe> {
+        let first = self.keyframes.first()?;
+        let last = self.keyframes.last()?;
+        if self.keyframes.len() == 1 || time <= first.time {
+            return Some(CameraKeyframe {
+                time,
+                ..first.clone()
+            });
+        }
+        if time >= last.time {
+            return Some(CameraKeyframe {
+                time,
+                ..last.clone()
+            });
+        }
+
+        let segment = self
+            .keyframes
+            .windows(2)
+            .position(|pair| time < pair[1].time)
+            .unwrap_or(self.keyframes.len() - 2);
+        let k1 = &self.keyframes[segment];
+        let k2 = &self.keyframes[segment + 1];
+        // En los extremos se repite el keyframe como punto de control
+        let k0 = &self.keyframes[segment.saturating_sub(1)];
+        let k3 = &self.keyframes[(segment + 2).min(self.keyframes.len() - 1)];
+
+        let span = k2.time - k1.time;
+        let t = if span > 0.0 {
+            (time - k1.time) / span
+        } else {
+            0.0
+        };
+
+        let eye = catmull_rom(&k0.eye, &k1.eye, &k2.eye, &k3.eye, t);
+        let lens = catmull_rom(
+            &k0.lens_parameters(),
+            &k1.lens_parameters(),
+            &k2.lens_parameters(),
+            &k3.lens_parameters(),
+            t,
+        );
+
+        let q1 = k1.orientation(up);
+        let q2 = k2.orientation(up);
+        let orientation = q1.try_slerp(&q2, t, 1e-6).unwrap_or(q1);
+        let forward = orientation * Vec3::z();
+
+        Some(CameraKeyframe {
+            time,
+            eye,
+            center: eye + forward * lens.z.max(1e-3),
+            fov: lens.x,
+            aperture: lens.y.max(0.0),
+        })
+    }
+
+    // Mueve la cámara al estado del recorrido en `time`
+    pub fn apply(&self, camera: &mut Camera, time: f32) {
+        if let Some(keyframe) = self.sample(time, &camera.up) {
+            camera.eye = keyframe.eye;
+            camera.center = keyframe.center;
+            camera.set_fov(keyframe.fov);
+            camera.aperture = keyframe.aperture;
+        }
+    }
+
+    // Formato de texto: una línea por keyframe con
+    // `tiempo ojo.x ojo.y ojo.z centro.x centro.y centro.z fov apertura`
+    pub fn load(file_path: &str) -> io::Result<Self> {
+        let mut path = CameraPath::new();
+        for (number, line) in data_lines(&fs::read_to_string(file_path)?) {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let values = parse_floats(number, &fields)?;
+            if values.len() != 9 {
+                return Err(invalid_line(number, "se esperaban 9 valores"));
+            }
+            path.push(CameraKeyframe {
+                time: values[0],
+                eye: Vec3::new(values[1], values[2], values[3]),
+                center: Vec3::new(values[4], values[5], values[6]),
+                fov: values[7],
+                aperture: values[8],
+            });
+        }
+        Ok(path)
+    }
+
+    pub fn save(&self, file_path: &str) -> io::Result<()> {
+        let mut contents =
+            String::from("# tiempo ojo.x ojo.y ojo.z centro.x centro.y centro.z fov apertura\n");
+        for k in &self.keyframes {
+            contents.push_str(&format!(
+                "{} {} {} {} {} {} {} {} {}\n",
+                k.time,
+                k.eye.x,
+                k.eye.y,
+                k.eye.z,
+                k.center.x,
+                k.center.y,
+                k.center.z,
+                k.fov,
+                k.aperture
+            ));
+        }
+        fs::write(file_path, contents)
+    }
+}
+
+// Spline de Catmull-Rom uniforme entre p1 y p2
+fn catmull_rom(p0: &Vec3, p1: &Vec3, p2: &Vec3, p3: &Vec3, t: f32) -> Vec3 {
+    let t2 = t * t;
+    let t3 = t2 * t;
+    (p1 * 2.0
+        + (p2 - p0) * t
+        + (p0 * 2.0 - p1 * 5.0 + p2 * 4.0 - p3) * t2
+        + (p1 * 3.0 - p0 - p2 * 3.0 + p3) * t3)
+        * 0.5
+}
@@ -1,3 +1,4 @@
+use nalgebra_glm::Vec3;
 use std::fmt;
 use std::ops::{Add, AddAssign, Mul};
 
@@ -39,7 +40,50 @@ impl Color {
         }
     }
 
-    pub fn to_hex(&self) -> u32 {
+    // Canales como flotantes en [0, 255], útil para acumular muestras
+    pub fn to_vec3(self) -> Vec3 {
+        Vec3::new(self.r as f32, self.g as f32, self.b as f32)
+    }
+
+    pub fn from_vec3(v: &Vec3) -> Self {
+        Color::new(v.x.round() as i32, v.y.round() as i32, v.z.round() as i32)
+    }
+
+    // Multiplica cada canal por su factor en `filter` (r, g, b)
+    pub fn filter(self, filter: &Vec3) -> Self {
+        Self {
+            r: ((self.r as f32 * filter.x).clamp(0.0, 255.0)) as u8,
+            g: ((self.g as f32 * filter.y).clamp(0.0, 255.0)) as u8,
+            b: ((self.b as f32 * filter.z).clamp(0.0, 255.0)) as u8,
+        }
+    }
+
+    // Escala de calor para vistas de depuración: `t` en [0, 1] va de azul a rojo
+    pub fn heatmap(t: f32) -> Self {
+        let t = t.clamp(0.0, 1.0) * 4.0;
+        let (r, g, b) = match t as u32 {
+            0 => (0.0, t, 1.0),
+            1 => (0.0, 1.0, 2.0 - t),
+            2 => (t - 2.0, 1.0, 0.0),
+            _ => (1.0, (4.0 - t).max(0.0), 0.0),
+        };
+        Color::from_vec3(&(Vec3::new(r, g, b) * 255.0))
+    }
+
+    // Color distintivo y estable para un identificador (objeto, material...)
+    pub fn from_id(id: u64) -> Self {
+        let hash = id.wrapping_add(1).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+        let channel = |shift: u32| 64 + ((hash >> shift) & 0xFF) as i32 * 3 / 4;
+        Color::new(channel(16), channel(32), channel(48))
+    }
+
+    // Mezcla lineal hacia `other`; `t` = 0 conserva el color, 1 lo reemplaza
+    pub fn blend(self, other: Color, t: f32) -> Self {
+        let t = t.clamp(0.0, 1.0);
+        Color::from_vec3(&(self.to_vec3() * (1.0 - t) + other.to_vec3() * t))
+    }
+
+    pub fn to_hex(self) -> u32 {
         ((self.r as u32) << 16) | ((self.g as u32) << 8) | (self.b as u32)
     }
 }
@@ -2,6 +2,9 @@ use crate::material::Material;
 use crate::ray_intersect::{Intersect, RayIntersect};
 use nalgebra_glm::Vec3;
 
+// Componentes de la dirección menores a esto se tratan como paralelas al eje
+const PARALLEL_EPSILON: f32 = 1e-12;
+
 pub struct Cuboid {
     pub center: Vec3,
     pub width: f32,
@@ -21,37 +24,39 @@ impl Cuboid {
         }
     }
 
-    pub fn get_uv(&self, point: &Vec3, normal: &Vec3) -> (f32, f32) {
+    // Coordenadas de textura de la cara que contiene `point`, con la caja en `center` y de
+    // tamaño `size`
+    pub fn get_uv(point: &Vec3, normal: &Vec3, center: &Vec3, size: &Vec3) -> (f32, f32) {
         // Use different UV calculations for each face
         if normal.x > 0.9 {
             // Right face
-            let u = (point.z - (self.center.z - self.depth / 2.0)) / self.depth;
-            let v = (point.y - (self.center.y - self.height / 2.0)) / self.height;
+            let u = (point.z - (center.z - size.z / 2.0)) / size.z;
+            let v = (point.y - (center.y - size.y / 2.0)) / size.y;
             return (1.0 - u, v);
         } else if normal.x < -0.9 {
             // Left face
-            let u = (point.z - (self.center.z - self.depth / 2.0)) / self.depth;
-            let v = (point.y - (self.center.y - self.height / 2.0)) / self.height;
+            let u = (point.z - (center.z - size.z / 2.0)) / size.z;
+            let v = (point.y - (center.y - size.y / 2.0)) / size.y;
             return (u, v);
         } else if normal.y > 0.9 {
             // Top face
-            let u = (point.x - (self.center.x - self.width / 2.0)) / self.width;
-            let v = (point.z - (self.center.z - self.depth / 2.0)) / self.depth;
+            let u = (point.x - (center.x - size.x / 2.0)) / size.x;
+            let v = (point.z - (center.z - size.z / 2.0)) / size.z;
             return (u, 1.0 - v);
         } else if normal.y < -0.9 {
             // Bottom face
-            let u = (point.x - (self.center.x - self.width / 2.0)) / self.width;
-            let v = (point.z - (self.center.z - self.depth / 2.0)) / self.depth;
+            let u = (point.x - (center.x - size.x / 2.0)) / size.x;
+            let v = (point.z - (center.z - size.z / 2.0)) / size.z;
             return (u, v);
         } else if normal.z > 0.9 {
             // Front face
-            let u = (point.x - (self.center.x - self.width / 2.0)) / self.width;
-            let v = (point.y - (self.center.y - self.height / 2.0)) / self.height;
+            let u = (point.x - (center.x - size.x / 2.0)) / size.x;
+            let v = (point.y - (center.y - size.y / 2.0)) / size.y;
             return (u, 1.0 - v);
         } else if normal.z < -0.9 {
             // Back face
-            let u = (point.x - (self.center.x - self.width / 2.0)) / self.width;
-            let v = (point.y - (self.center.y - self.height / 2.0)) / self.height;
+            let u = (point.x - (center.x - size.x / 2.0)) / size.x;
+            let v = (point.y - (center.y - size.y / 2.0)) / size.y;
             return (1.0 - u, 1.0 - v);
         }
 
@@ -61,93 +66,100 @@ impl Cuboid {
 }
 
 impl RayIntersect for Cuboid {
-    fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Intersect {
-        let min = self.center - Vec3::new(self.width / 2.0, self.height / 2.0, self.depth / 2.0);
-        let max = self.center + Vec3::new(self.width / 2.0, self.height / 2.0, self.depth / 2.0);
-
-        // Calculate intersection distances for the x-axis
-        let mut tmin = (min.x - ray_origin.x) / ray_direction.x;
-        let mut tmax = (max.x - ray_origin.x) / ray_direction.x;
-
-        if tmin > tmax {
-            std::mem::swap(&mut tmin, &mut tmax);
-        }
-
-        // Calculate intersection distances for the y-axis
-        let mut tymin = (min.y - ray_origin.y) / ray_direction.y;
-        let mut tymax = (max.y - ray_origin.y) / ray_direction.y;
-
-        if tymin > tymax {
-            std::mem::swap(&mut tymin, &mut tymax);
+    fn ray_intersect_at(
+        &self,
+        ray_origin: &Vec3,
+        ray_direction: &Vec3,
+        center: &Vec3,
+        size: &Vec3,
+    ) -> Intersect {
+        let half_size = size / 2.0;
+        let min = center - half_size;
+        let max = center + half_size;
+
+        // Método de las placas: el intervalo [near, far] en que el rayo está dentro de las
+        // tres placas, recordando por qué eje entra y por cuál sale
+        let mut near = f32::NEG_INFINITY;
+        let mut far = f32::INFINITY;
+        let mut near_axis = 0;
+        let mut far_axis = 0;
+
+        for axis in 0..3 {
+            let origin = ray_origin[axis];
+            let direction = ray_direction[axis];
+
+            // Paralelo a la placa: dividir daría infinitos o NaN; el rayo está siempre
+            // dentro de ella o nunca
+            if direction.abs() < PARALLEL_EPSILON {
+                if origin < min[axis] || origin > max[axis] {
+                    return Intersect::empty();
+                }
+                continue;
+            }
+
+            let mut t0 = (min[axis] - origin) / direction;
+            let mut t1 = (max[axis] - origin) / direction;
+            if t0 > t1 {
+                std::mem::swap(&mut t0, &mut t1);
+            }
+            if t0 > near {
+                near = t0;
+                near_axis = axis;
+            }
+            if t1 < far {
+                far = t1;
+                far_axis = axis;
+            }
         }
 
-        // If there is no overlap between x and y intervals, return no intersection
-        if (tmin > tymax) || (tymin > tmax) {
+        // `far` infinito solo pasa con una dirección nula
+        if near > far || far < 0.0 || !far.is_finite() {
             return Intersect::empty();
         }
 
-        // Adjust tmin to be the maximum of the two, ensuring it's the farthest near intersection
-        if tymin > tmin {
-            tmin = tymin;
-        }
-
-        // Adjust tmax to be the minimum of the two, ensuring it's the closest far intersection
-        if tymax < tmax {
-            tmax = tymax;
-        }
-
-        // Calculate intersection distances for the z-axis
-        let mut tzmin = (min.z - ray_origin.z) / ray_direction.z;
-        let mut tzmax = (max.z - ray_origin.z) / ray_direction.z;
-
-        if tzmin > tzmax {
-            std::mem::swap(&mut tzmin, &mut tzmax);
-        }
+        // Con el origen dentro de la caja el impacto es la cara por la que sale. La normal
+        // siempre apunta hacia afuera: contra el rayo al entrar y con él al salir
+        let (distance, axis, outward) = if near >= 0.0 {
+            (near, near_axis, -1.0)
+        } else {
+            (far, far_axis, 1.0)
+        };
+        let mut normal = Vec3::zeros();
+        normal[axis] = outward * ray_direction[axis].signum();
 
-        // Check for overlap with the z-axis interval
-        if (tmin > tzmax) || (tzmin > tmax) {
-            return Intersect::empty();
-        }
+        let hit_point = ray_origin + ray_direction * distance;
+        let (u, v) = Cuboid::get_uv(&hit_point, &normal, center, size);
 
-        // Further adjust tmin and tmax based on the z values
-        if tzmin > tmin {
-            tmin = tzmin;
-        }
+        Intersect::new(hit_point, normal, distance, self.material.clone(), u, v)
+    }
 
-        // If tmax is not used after this point, you can omit this check.
-        // If it needs to be adjusted correctly, this line ensures the proper overlap.
-        // Otherwise, the assignment can be completely removed if it's never utilized:
-        // `tmax = tzmax;` can be omitted if not needed for further processing.
+    fn set_center(&mut self, center: Vec3) {
+        self.center = center;
+    }
 
-        // The actual intersection distance is represented by `tmin`
-        let distance = tmin;
+    fn set_size(&mut self, size: Vec3) {
+        self.width = size.x;
+        self.height = size.y;
+        self.depth = size.z;
+    }
 
-        if distance < 0.0 {
-            return Intersect::empty();
-        }
+    fn material_mut(&mut self) -> &mut Material {
+        &mut self.material
+    }
 
-        // Calculate the intersection point and normal
-        let hit_point = ray_origin + ray_direction * distance;
-        let mut normal = Vec3::zeros();
+    fn center(&self) -> Vec3 {
+        self.center
+    }
 
-        // Determine which face was hit based on the intersection point
-        if (hit_point.x - min.x).abs() < f32::EPSILON {
-            normal = Vec3::new(-1.0, 0.0, 0.0); // left face
-        } else if (hit_point.x - max.x).abs() < f32::EPSILON {
-            normal = Vec3::new(1.0, 0.0, 0.0); // right face
-        } else if (hit_point.y - min.y).abs() < f32::EPSILON {
-            normal = Vec3::new(0.0, -1.0, 0.0); // bottom face
-        } else if (hit_point.y - max.y).abs() < f32::EPSILON {
-            normal = Vec3::new(0.0, 1.0, 0.0); // top face
-        } else if (hit_point.z - min.z).abs() < f32::EPSILON {
-            normal = Vec3::new(0.0, 0.0, -1.0); // back face
-        } else if (hit_point.z - max.z).abs() < f32::EPSILON {
-            normal = Vec3::new(0.0, 0.0, 1.0); // front face
-        }
+    fn size(&self) -> Vec3 {
+        Vec3::new(self.width, self.height, self.depth)
+    }
 
-        // Get UV coordinates based on the hit point and face normal
-        let (u, v) = self.get_uv(&hit_point, &normal);
+    fn material(&self) -> &Material {
+        &self.material
+    }
 
-        Intersect::new(hit_point, normal, distance, self.material.clone(), u, v)
+    fn kind(&self) -> &'static str {
+        "cuboide"
     }
 }
@@ -0,0 +1,164 @@
+use crate::color::Color;
+use crate::framebuffer::Framebuffer;
+use nalgebra_glm::Vec3;
+use rayon::prelude::*;
+
+// Pasadas del filtro à-trous; el paso entre muestras se duplica en cada una
+const ATROUS_ITERATIONS: u32 = 4;
+// Coeficientes del spline B3 que usa el filtro à-trous
+const ATROUS_KERNEL: [f32; 5] = [1.0 / 16.0, 1.0 / 4.0, 3.0 / 8.0, 1.0 / 4.0, 1.0 / 16.0];
+// Radio de la ventana del filtro bilateral conjunto, en píxeles
+const BILATERAL_RADIUS: isize = 3;
+
+// Información de la primera superficie vista en cada píxel, para guiar el filtro
+pub struct FeatureBuffers {
+    pub width: usize,
+    pub height: usize,
+    pub albedo: Vec<Vec3>, // Color difuso sin iluminar, en [0, 1]
+    pub normal: Vec<Vec3>,
+    pub depth: Vec<f32>, // Distancia desde la cámara
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DenoiseFilter {
+    ATrous,         // Ondículas à-trous: rápido y de gran alcance
+    JointBilateral, // Ventana densa pequeña: más lento, conserva mejor los detalles
+}
+
+impl DenoiseFilter {
+    pub fn from_name(name: &str) -> Option<DenoiseFilter> {
+        match name {
+            "atrous" => Some(DenoiseFilter::ATrous),
+            "bilateral" => Some(DenoiseFilter::JointBilateral),
+            _ => None,
+        }
+    }
+}
+
+// Filtro que suaviza el ruido sin cruzar bordes de geometría ni de textura
+#[derive(Debug, Clone, Copy)]
+pub struct Denoiser {
+    pub filter: DenoiseFilter,
+    pub color_sigma: f32, // Tolerancia a diferencias de color, en [0, 1]
+    pub normal_sigma: f32,
+    pub albedo_sigma: f32,
+    pub depth_sigma: f32, // Diferencia de profundidad relativa tolerada
+}
+
+impl Denoiser {
+    pub fn new(filter: DenoiseFilter) -> Self {
+        Denoiser {
+            filter,
+            color_sigma: 0.5,
+            normal_sigma: 0.3,
+            albedo_sigma: 0.1,
+            depth_sigma: 0.05,
+        }
+    }
+
+    pub fn apply(&self, framebuffer: &mut Framebuffer, features: &FeatureBuffers) {
+        let mut colors: Vec<Vec3> = framebuffer
+            .buffer
+            .iter()
+            .map(|color| color.to_vec3() / 255.0)
+            .collect();
+
+        match self.filter {
+            DenoiseFilter::ATrous => {
+                for iteration in 0..ATROUS_ITERATIONS {
+                    let step = 1 << iteration;
+                    let mut taps = Vec::with_capacity(25);
+                    for (j, weight_y) in ATROUS_KERNEL.iter().enumerate() {
+                        for (i, weight_x) in ATROUS_KERNEL.iter().enumerate() {
+                            let dx = (i as isize - 2) * step;
+                            let dy = (j as isize - 2) * step;
+                            taps.push((dx, dy, weight_x * weight_y));
+                        }
+                    }
+                    // Las pasadas gruesas toleran menos diferencia de color
+                    let color_sigma = self.color_sigma / step as f32;
+                    colors = self.filter_pass(&colors, features, &taps, color_sigma);
+                }
+            }
+            DenoiseFilter::JointBilateral => {
+                let spatial_sigma = BILATERAL_RADIUS as f32 * 0.5;
+                let mut taps = Vec::new();
+                for dy in -BILATERAL_RADIUS..=BILATERAL_RADIUS {
+                    for dx in -BILATERAL_RADIUS..=BILATERAL_RADIUS {
+                        let distance_squared = (dx * dx + dy * dy) as f32;
+                        let weight =
+                            (-distance_squared / (2.0 * spatial_sigma * spatial_sigma)).exp();
+                        taps.push((dx, dy, weight));
+                    }
+                }
+                colors = self.filter_pass(&colors, features, &taps, self.color_sigma);
+            }
+        }
+
+        for (pixel, color) in framebuffer.buffer.iter_mut().zip(&colors) {
+            *pixel = Color::from_vec3(&(color * 255.0));
+        }
+    }
+
+    // Promedio ponderado de las muestras (dx, dy, peso espacial) alrededor de cada píxel
+    fn filter_pass(
+        &self,
+        colors: &[Vec3],
+        features: &FeatureBuffers,
+        taps: &[(isize, isize, f32)],
+        color_sigma: f32,
+    ) -> Vec<Vec3> {
+        let width = features.width;
+        let height = features.height;
+        let mut filtered = vec![Vec3::zeros(); colors.len()];
+
+        filtered
+            .par_chunks_mut(width)
+            .enumerate()
+            .for_each(|(y, row)| {
+                for (x, output) in row.iter_mut().enumerate() {
+                    let p = y * width + x;
+                    let mut sum = Vec3::zeros();
+                    let mut weight_sum = 0.0;
+
+                    for &(dx, dy, spatial_weight) in taps {
+                        let qx = x as isize + dx;
+                        let qy = y as isize + dy;
+                        if qx < 0 || qy < 0 || qx >= width as isize || qy >= height as isize {
+                            continue;
+                        }
+                        let q = qy as usize * width + qx as usize;
+                        let weight =
+                            spatial_weight * self.edge_weight(p, q, colors, features, color_sigma);
+                        sum += colors[q] * weight;
+                        weight_sum += weight;
+                    }
+
+                    // El propio píxel siempre aporta, así que weight_sum > 0
+                    *output = sum / weight_sum;
+                }
+            });
+
+        filtered
+    }
+
+    // Cuánto se parecen los píxeles p y q según el color y las superficies que ven
+    fn edge_weight(
+        &self,
+        p: usize,
+        q: usize,
+        colors: &[Vec3],
+        features: &FeatureBuffers,
+        color_sigma: f32,
+    ) -> f32 {
+        let color_distance = (colors[p] - colors[q]).norm_squared() / (color_sigma * color_sigma);
+        let normal_distance = (features.normal[p] - features.normal[q]).norm_squared()
+            / (self.normal_sigma * self.normal_sigma);
+        let albedo_distance = (features.albedo[p] - features.albedo[q]).norm_squared()
+            / (self.albedo_sigma * self.albedo_sigma);
+        let depth_distance = (features.depth[p] - features.depth[q]).abs()
+            / (self.depth_sigma * features.depth[p].max(1e-3));
+
+        (-(color_distance + normal_distance + albedo_distance + depth_distance)).exp()
+    }
+}
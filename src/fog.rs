@@ -0,0 +1,92 @@
+use crate::color::Color;
+use nalgebra_glm::Vec3;
+
+// Distancia usada para los rayos que no golpean nada (cielo)
+pub const SKY_DISTANCE: f32 = 50.0;
+
+// Medio homogéneo para dispersión simple (rayos de luz volumétricos)
+#[derive(Debug, Clone)]
+pub struct Volume {
+    pub scattering: f32,   // Coeficiente de dispersión del medio
+    pub anisotropy: f32,   // Parámetro g de Henyey-Greenstein (> 0 dispersa hacia adelante)
+    pub steps: u32,        // Muestras por rayo al marchar por el volumen
+    pub max_distance: f32, // Distancia máxima que se marcha dentro del volumen
+}
+
+impl Volume {
+    pub fn new(scattering: f32, anisotropy: f32, steps: u32, max_distance: f32) -> Self {
+        Volume {
+            scattering,
+            anisotropy,
+            steps,
+            max_distance,
+        }
+    }
+
+    // Función de fase de Henyey-Greenstein
+    pub fn phase(&self, cos_theta: f32) -> f32 {
+        let g = self.anisotropy;
+        let denom = (1.0 + g * g - 2.0 * g * cos_theta).max(1e-4);
+        (1.0 - g * g) / (4.0 * std::f32::consts::PI * denom * denom.sqrt())
+    }
+
+    pub fn transmittance(&self, distance: f32) -> f32 {
+        (-self.scattering * distance.min(self.max_distance)).exp()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Fog {
+    pub color: Color,
+    pub density: f32,        // Densidad de la niebla por distancia
+    pub height_density: f32, // Densidad de la niebla de altura en `base_height`
+    pub height_falloff: f32, // Qué tan rápido se disipa la niebla al subir
+    pub base_height: f32,
+    pub volume: Option<Volume>,
+}
+
+impl Fog {
+    pub fn new(
+        color: Color,
+        density: f32,
+        height_density: f32,
+        height_falloff: f32,
+        base_height: f32,
+    ) -> Self {
+        Fog {
+            color,
+            density,
+            height_density,
+            height_falloff,
+            base_height,
+            volume: None,
+        }
+    }
+
+    // Profundidad óptica integrada de forma analítica a lo largo del rayo
+    pub fn optical_depth(&self, origin: &Vec3, direction: &Vec3, distance: f32) -> f32 {
+        let distance_term = self.density * distance;
+
+        let falloff = self.height_falloff;
+        let start = self.height_density * (-falloff * (origin.y - self.base_height)).exp();
+        let slope = falloff * direction.y;
+        let height_term = if slope.abs() > 1e-4 {
+            start * (1.0 - (-slope * distance).exp()) / slope
+        } else {
+            start * distance
+        };
+
+        distance_term + height_term.max(0.0)
+    }
+
+    pub fn transmittance(&self, origin: &Vec3, direction: &Vec3, distance: f32) -> f32 {
+        (-self.optical_depth(origin, direction, distance)).exp()
+    }
+
+    // Mezcla la radiancia (en la escala de 0 a 255 de los colores) con la niebla según lo
+    // que recorrió el rayo
+    pub fn apply(&self, radiance: Vec3, origin: &Vec3, direction: &Vec3, distance: f32) -> Vec3 {
+        let transmittance = self.transmittance(origin, direction, distance);
+        radiance * transmittance + self.color.to_vec3() * (1.0 - transmittance)
+    }
+}
@@ -1,5 +1,7 @@
 use crate::color::Color;
+use image::RgbImage;
 
+#[allow(dead_code)]
 pub struct Framebuffer {
     pub width: usize,
     pub height: usize,
@@ -8,9 +10,10 @@ pub struct Framebuffer {
     pub current_color: Color,
 }
 
+#[allow(dead_code)]
 impl Framebuffer {
     pub fn new(width: usize, height: usize) -> Self {
-        let buffer = vec![Color::new(0, 0, 0); width * height];
+        let buffer = vec![Color::black(); width * height];
         Self {
             buffer,
             width,
@@ -44,14 +47,14 @@ impl Framebuffer {
     ) {
         for i in 0..width {
             for j in 0..height {
-                self.point_with_color(x + i, y + j, color.clone());
+                self.point_with_color(x + i, y + j, color);
             }
         }
     }
 
     pub fn clear(&mut self) {
         for pixel in &mut self.buffer {
-            *pixel = self.background_color.clone();
+            *pixel = self.background_color;
         }
     }
 
@@ -66,4 +69,40 @@ impl Framebuffer {
     pub fn to_u32_buffer(&self) -> Vec<u32> {
         self.buffer.iter().map(|color| color.to_hex()).collect()
     }
+
+    // Llena el framebuffer estirando `source` con interpolación bilineal
+    pub fn upscale_from(&mut self, source: &Framebuffer) {
+        let scale_x = source.width as f32 / self.width as f32;
+        let scale_y = source.height as f32 / self.height as f32;
+        let texel = |x: usize, y: usize| {
+            source.buffer[y.min(source.height - 1) * source.width + x.min(source.width - 1)]
+                .to_vec3()
+        };
+
+        for y in 0..self.height {
+            // Centros de píxel alineados entre ambas resoluciones
+            let source_y = ((y as f32 + 0.5) * scale_y - 0.5).max(0.0);
+            let y0 = source_y as usize;
+            let ty = source_y - y0 as f32;
+            for x in 0..self.width {
+                let source_x = ((x as f32 + 0.5) * scale_x - 0.5).max(0.0);
+                let x0 = source_x as usize;
+                let tx = source_x - x0 as f32;
+
+                let top = texel(x0, y0) * (1.0 - tx) + texel(x0 + 1, y0) * tx;
+                let bottom = texel(x0, y0 + 1) * (1.0 - tx) + texel(x0 + 1, y0 + 1) * tx;
+                self.buffer[y * self.width + x] =
+                    Color::from_vec3(&(top * (1.0 - ty) + bottom * ty));
+            }
+        }
+    }
+
+    // Guarda el contenido en un archivo de imagen (formato según la extensión)
+    pub fn save(&self, file_path: &str) -> image::ImageResult<()> {
+        let image = RgbImage::from_fn(self.width as u32, self.height as u32, |x, y| {
+            let color = self.buffer[y as usize * self.width + x as usize];
+            image::Rgb([color.r, color.g, color.b])
+        });
+        image.save(file_path)
+    }
 }
@@ -0,0 +1,567 @@
+// Capa de acciones del visor: cada acción se asigna a teclas o botones del ratón y
+// se consulta sin importar qué entrada la activó
+use crate::text_format::{data_lines, invalid_line, parse_floats};
+use minifb::{Key, MouseButton, Window};
+use std::fs;
+use std::io;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    Quit,
+    OrbitLeft,
+    OrbitRight,
+    OrbitUp,
+    OrbitDown,
+    ZoomIn,
+    ZoomOut,
+    FlyForward,
+    FlyBack,
+    FlyLeft,
+    FlyRight,
+    FlyUp,
+    FlyDown,
+    FlyFast,
+    FlySlow,
+    LookLeft,
+    LookRight,
+    LookUp,
+    LookDown,
+    MouseLook,
+    SunBack,
+    SunForward,
+    FlySunBack,
+    FlySunForward,
+    FovIn,
+    FovOut,
+    ApertureOpen,
+    ApertureClose,
+    NextBokeh,
+    NextProjection,
+    ToggleCameraMode,
+    RecordKeyframe,
+    TogglePlayback,
+    ToggleTimeline,
+    Slot(usize), // Marcador o material del modo edición
+    SaveModifier,
+    Select,
+    RemoveBlock,
+    PaintBlock,
+    ToggleEdit,
+    SaveScene,
+    Capture,
+    CaptureAovs,
+    CapturePasses,
+    NextRenderMode,
+    TogglePreview,
+    ToggleAdaptive,
+    NextDenoiser,
+    ToggleFog,
+    ToggleVolumetrics,
+    ToggleHelp,
+}
+
+// Modo del visor en que se usa una acción. Las acciones de modos que no pueden estar
+// activos a la vez comparten teclas sin conflicto (W acerca en órbita y avanza en vuelo)
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Mode {
+    Any,
+    Orbit,
+    FreeFly,
+    FlyView, // Vuelo libre fuera del modo edición
+    Edit,
+}
+
+impl Mode {
+    fn excludes(self, other: Mode) -> bool {
+        use Mode::*;
+        matches!(
+            (self, other),
+            (Orbit, FreeFly | FlyView)
+                | (FreeFly | FlyView, Orbit)
+                | (FlyView, Edit)
+                | (Edit, FlyView)
+        )
+    }
+}
+
+impl Action {
+    fn mode(self) -> Mode {
+        use Action::*;
+        match self {
+            OrbitLeft | OrbitRight | OrbitUp | OrbitDown | ZoomIn | ZoomOut | SunBack
+            | SunForward => Mode::Orbit,
+            FlyForward | FlyBack | FlyLeft | FlyRight | FlyUp | FlyDown | FlyFast | FlySlow
+            | LookLeft | LookRight | LookUp | LookDown | FlySunBack | FlySunForward => {
+                Mode::FreeFly
+            }
+            MouseLook => Mode::FlyView,
+            RemoveBlock | PaintBlock => Mode::Edit,
+            _ => Mode::Any,
+        }
+    }
+}
+
+// Cuándo se considera activa una acción
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Trigger {
+    Pressed, // Solo en el cuadro en que se presiona
+    Held,    // Mientras se mantenga presionada
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Binding {
+    Key(Key),
+    Mouse(MouseButton),
+}
+
+impl Binding {
+    fn is_down(&self, window: &Window) -> bool {
+        match *self {
+            Binding::Key(key) => window.is_key_down(key),
+            Binding::Mouse(button) => window.get_mouse_down(button),
+        }
+    }
+
+    // Nombres de los archivos de controles: los de minifb y MouseLeft/Right/Middle
+    pub fn from_name(name: &str) -> Option<Binding> {
+        let button = match name {
+            "MouseLeft" => Some(MouseButton::Left),
+            "MouseRight" => Some(MouseButton::Right),
+            "MouseMiddle" => Some(MouseButton::Middle),
+            _ => None,
+        };
+        if let Some(button) = button {
+            return Some(Binding::Mouse(button));
+        }
+        KEYS.iter()
+            .find(|key| format!("{:?}", key) == name)
+            .map(|key| Binding::Key(*key))
+    }
+
+    pub fn name(&self) -> String {
+        match self {
+            Binding::Key(key) => format!("{:?}", key),
+            Binding::Mouse(button) => format!("Mouse{:?}", button),
+        }
+    }
+}
+
+// Teclas que se pueden nombrar en el archivo de controles: todas las de minifb salvo
+// Unknown, en el orden de su enum
+const KEYS: [Key; 106] = [
+    Key::Key0,
+    Key::Key1,
+    Key::Key2,
+    Key::Key3,
+    Key::Key4,
+    Key::Key5,
+    Key::Key6,
+    Key::Key7,
+    Key::Key8,
+    Key::Key9,
+    Key::A,
+    Key::B,
+    Key::C,
+    Key::D,
+    Key::E,
+    Key::F,
+    Key::G,
+    Key::H,
+    Key::I,
+    Key::J,
+    Key::K,
+    Key::L,
+    Key::M,
+    Key::N,
+    Key::O,
+    Key::P,
+    Key::Q,
+    Key::R,
+    Key::S,
+    Key::T,
+    Key::U,
+    Key::V,
+    Key::W,
+    Key::X,
+    Key::Y,
+    Key::Z,
+    Key::F1,
+    Key::F2,
+    Key::F3,
+    Key::F4,
+    Key::F5,
+    Key::F6,
+    Key::F7,
+    Key::F8,
+    Key::F9,
+    Key::F10,
+    Key::F11,
+    Key::F12,
+    Key::F13,
+    Key::F14,
+    Key::F15,
+    Key::Down,
+    Key::Left,
+    Key::Right,
+    Key::Up,
+    Key::Apostrophe,
+    Key::Backquote,
+    Key::Backslash,
+    Key::Comma,
+    Key::Equal,
+    Key::LeftBracket,
+    Key::Minus,
+    Key::Period,
+    Key::RightBracket,
+    Key::Semicolon,
+    Key::Slash,
+    Key::Backspace,
+    Key::Delete,
+    Key::End,
+    Key::Enter,
+    Key::Escape,
+    Key::Home,
+    Key::Insert,
+    Key::Menu,
+    Key::PageDown,
+    Key::PageUp,
+    Key::Pause,
+    Key::Space,
+    Key::Tab,
+    Key::NumLock,
+    Key::CapsLock,
+    Key::ScrollLock,
+    Key::LeftShift,
+    Key::RightShift,
+    Key::LeftCtrl,
+    Key::RightCtrl,
+    Key::NumPad0,
+    Key::NumPad1,
+    Key::NumPad2,
+    Key::NumPad3,
+    Key::NumPad4,
+    Key::NumPad5,
+    Key::NumPad6,
+    Key::NumPad7,
+    Key::NumPad8,
+    Key::NumPad9,
+    Key::NumPadDot,
+    Key::NumPadSlash,
+    Key::NumPadAsterisk,
+    Key::NumPadMinus,
+    Key::NumPadPlus,
+    Key::NumPadEnter,
+    Key::LeftAlt,
+    Key::RightAlt,
+    Key::LeftSuper,
+    Key::RightSuper,
+];
+// Falla al compilar si una versión nueva de minifb agrega teclas que falten aquí
+const _: () = assert!(KEYS.len() == Key::Unknown as usize);
+
+const DIGIT_KEYS: [Key; 10] = [
+    Key::Key0,
+    Key::Key1,
+    Key::Key2,
+    Key::Key3,
+    Key::Key4,
+    Key::Key5,
+    Key::Key6,
+    Key::Key7,
+    Key::Key8,
+    Key::Key9,
+];
+
+// Acción con sus entradas, cómo se activa y cuánto avanza por cuadro
+#[derive(Debug, Clone)]
+struct ActionBinding {
+    action: Action,
+    name: String,
+    trigger: Trigger,
+    bindings: Vec<Binding>,
+    step: f32,
+}
+
+pub struct Input {
+    actions: Vec<ActionBinding>,
+    down: Vec<bool>,     // Estado en este cuadro, en el orden de `actions`
+    previous: Vec<bool>, // Estado en el cuadro anterior
+}
+
+impl Default for Input {
+    // Controles originales del visor
+    fn default() -> Self {
+        use Action::*;
+        use Trigger::*;
+        let key = |key: Key| vec![Binding::Key(key)];
+        let mut actions = vec![
+            entry(Quit, "quit", Held, key(Key::Escape), 1.0),
+            entry(OrbitLeft, "orbit_left", Held, key(Key::Left), 0.05),
+            entry(OrbitRight, "orbit_right", Held, key(Key::Right), 0.05),
+            entry(OrbitUp, "orbit_up", Held, key(Key::Up), 0.05),
+            entry(OrbitDown, "orbit_down", Held, key(Key::Down), 0.05),
+            entry(ZoomIn, "zoom_in", Held, key(Key::W), 0.1),
+            entry(ZoomOut, "zoom_out", Held, key(Key::S), 0.1),
+            entry(FlyForward, "fly_forward", Held, key(Key::W), 1.0),
+            entry(FlyBack, "fly_back", Held, key(Key::S), 1.0),
+            entry(FlyLeft, "fly_left", Held, key(Key::A), 1.0),
+            entry(FlyRight, "fly_right", Held, key(Key::D), 1.0),
+            entry(FlyUp, "fly_up", Held, key(Key::Space), 1.0),
+            // Shift queda libre para guardar marcadores mientras se vuela
+            entry(FlyDown, "fly_down", Held, key(Key::Z), 1.0),
+            entry(FlyFast, "fly_fast", Held, key(Key::LeftCtrl), 4.0),
+            entry(FlySlow, "fly_slow", Held, key(Key::LeftAlt), 0.25),
+            entry(LookLeft, "look_left", Held, key(Key::Left), 1.0),
+            entry(LookRight, "look_right", Held, key(Key::Right), 1.0),
+            entry(LookUp, "look_up", Held, key(Key::Up), 1.0),
+            entry(LookDown, "look_down", Held, key(Key::Down), 1.0),
+            entry(
+                MouseLook,
+                "mouse_look",
+                Held,
+                vec![Binding::Mouse(MouseButton::Right)],
+                1.0,
+            ),
+            entry(SunBack, "sun_back", Held, key(Key::A), 0.05),
+            entry(SunForward, "sun_forward", Held, key(Key::D), 0.05),
+            entry(FlySunBack, "fly_sun_back", Held, key(Key::Q), 0.05),
+            entry(FlySunForward, "fly_sun_forward", Held, key(Key::E), 0.05),
+            entry(FovIn, "fov_in", Held, key(Key::Equal), 1.0),
+            entry(FovOut, "fov_out", Held, key(Key::Minus), 1.0),
+            entry(
+                ApertureOpen,
+                "aperture_open",
+                Held,
+                key(Key::RightBracket),
+                0.005,
+            ),
+            entry(
+                ApertureClose,
+                "aperture_close",
+                Held,
+                key(Key::LeftBracket),
+                0.005,
+            ),
+            entry(NextBokeh, "next_bokeh", Pressed, key(Key::B), 1.0),
+            entry(NextProjection, "next_projection", Pressed, key(Key::P), 1.0),
+            entry(
+                ToggleCameraMode,
+                "toggle_camera_mode",
+                Pressed,
+                key(Key::C),
+                1.0,
+            ),
+            entry(RecordKeyframe, "record_keyframe", Pressed, key(Key::K), 1.0),
+            entry(TogglePlayback, "toggle_playback", Pressed, key(Key::L), 1.0),
+            entry(ToggleTimeline, "toggle_timeline", Pressed, key(Key::T), 1.0),
+            entry(
+                SaveModifier,
+                "save_modifier",
+                Held,
+                vec![Binding::Key(Key::LeftShift), Binding::Key(Key::RightShift)],
+                1.0,
+            ),
+            entry(
+                Select,
+                "select",
+                Pressed,
+                vec![Binding::Mouse(MouseButton::Left)],
+                1.0,
+            ),
+            entry(
+                RemoveBlock,
+                "remove_block",
+                Pressed,
+                vec![Binding::Mouse(MouseButton::Right)],
+                1.0,
+            ),
+            entry(
+                PaintBlock,
+                "paint_block",
+                Pressed,
+                vec![Binding::Mouse(MouseButton::Middle)],
+                1.0,
+            ),
+            entry(ToggleEdit, "toggle_edit", Pressed, key(Key::Tab), 1.0),
+            entry(SaveScene, "save_scene", Pressed, key(Key::F5), 1.0),
+            entry(Capture, "capture", Pressed, key(Key::F2), 1.0),
+            entry(CaptureAovs, "capture_aovs", Pressed, key(Key::F3), 1.0),
+            entry(CapturePasses, "capture_passes", Pressed, key(Key::F4), 1.0),
+            entry(
+                NextRenderMode,
+                "next_render_mode",
+                Pressed,
+                key(Key::M),
+                1.0,
+            ),
+            entry(TogglePreview, "toggle_preview", Pressed, key(Key::R), 1.0),
+            entry(ToggleAdaptive, "toggle_adaptive", Pressed, key(Key::V), 1.0),
+            entry(NextDenoiser, "next_denoiser", Pressed, key(Key::N), 1.0),
+            entry(ToggleFog, "toggle_fog", Pressed, key(Key::F), 1.0),
+            entry(
+                ToggleVolumetrics,
+                "toggle_volumetrics",
+                Pressed,
+                key(Key::G),
+                1.0,
+            ),
+            entry(ToggleHelp, "toggle_help", Pressed, key(Key::H), 1.0),
+        ];
+        for (slot, digit) in DIGIT_KEYS.iter().enumerate() {
+            actions.push(ActionBinding {
+                action: Slot(slot),
+                name: format!("slot_{}", slot),
+                trigger: Pressed,
+                bindings: key(*digit),
+                step: 1.0,
+            });
+        }
+
+        Input {
+            down: vec![false; actions.len()],
+            previous: vec![false; actions.len()],
+            actions,
+        }
+    }
+}
+
+fn entry(
+    action: Action,
+    name: &str,
+    trigger: Trigger,
+    bindings: Vec<Binding>,
+    step: f32,
+) -> ActionBinding {
+    ActionBinding {
+        action,
+        name: name.to_string(),
+        trigger,
+        bindings,
+        step,
+    }
+}
+
+impl Input {
+    // Formato de texto, sobre los controles por defecto:
+    //   `acción entrada...` reemplaza las entradas (ninguna la desactiva)
+    //   `step acción valor` cambia cuánto avanza la acción por cuadro
+    pub fn load(file_path: &str) -> io::Result<Self> {
+        let mut input = Input::default();
+        for (number, line) in data_lines(&fs::read_to_string(file_path)?) {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields[0] == "step" {
+                if fields.len() != 3 {
+                    return Err(invalid_line(number, "se esperaba `step acción valor`"));
+                }
+                let step = parse_floats(number, &fields[2..3])?[0];
+                input.find_mut(number, fields[1])?.step = step;
+                continue;
+            }
+
+            let bindings = fields[1..]
+                .iter()
+                .map(|name| {
+                    Binding::from_name(name).ok_or_else(|| {
+                        invalid_line(number, &format!("entrada desconocida: {}", name))
+                    })
+                })
+                .collect::<io::Result<Vec<_>>>()?;
+            input.find_mut(number, fields[0])?.bindings = bindings;
+        }
+        input.check_conflicts()?;
+        Ok(input)
+    }
+
+    // Dos acciones que pueden estar activas a la vez no deben compartir una entrada
+    fn check_conflicts(&self) -> io::Result<()> {
+        for (index, first) in self.actions.iter().enumerate() {
+            for second in &self.actions[index + 1..] {
+                if first.action.mode().excludes(second.action.mode()) {
+                    continue;
+                }
+                if let Some(binding) = first
+                    .bindings
+                    .iter()
+                    .find(|binding| second.bindings.contains(binding))
+                {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!(
+                            "{} está asignada a {} y a {}",
+                            binding.name(),
+                            first.name,
+                            second.name
+                        ),
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn find_mut(&mut self, number: usize, name: &str) -> io::Result<&mut ActionBinding> {
+        self.actions
+            .iter_mut()
+            .find(|binding| binding.name == name)
+            .ok_or_else(|| invalid_line(number, &format!("acción desconocida: {}", name)))
+    }
+
+    fn index(&self, action: Action) -> Option<usize> {
+        self.actions
+            .iter()
+            .position(|binding| binding.action == action)
+    }
+
+    // Lee el estado de todas las entradas; se llama una vez por cuadro
+    pub fn update(&mut self, window: &Window) {
+        std::mem::swap(&mut self.previous, &mut self.down);
+        for (down, binding) in self.down.iter_mut().zip(&self.actions) {
+            *down = binding.bindings.iter().any(|input| input.is_down(window));
+        }
+    }
+
+    pub fn held(&self, action: Action) -> bool {
+        self.index(action).is_some_and(|index| self.down[index])
+    }
+
+    // Activa según su disparador: al presionar o mientras se mantenga
+    pub fn active(&self, action: Action) -> bool {
+        let Some(index) = self.index(action) else {
+            return false;
+        };
+        match self.actions[index].trigger {
+            Trigger::Held => self.down[index],
+            Trigger::Pressed => self.down[index] && !self.previous[index],
+        }
+    }
+
+    // Paso configurado si la acción está activa, 0 si no
+    pub fn amount(&self, action: Action) -> f32 {
+        match self.index(action) {
+            Some(index) if self.active(action) => self.actions[index].step,
+            _ => 0.0,
+        }
+    }
+
+    // Eje entre dos acciones opuestas: `positive` suma y `negative` resta
+    pub fn axis(&self, negative: Action, positive: Action) -> f32 {
+        self.amount(positive) - self.amount(negative)
+    }
+
+    // Entradas asignadas, para el panel de ayuda
+    pub fn describe(&self, action: Action) -> String {
+        let Some(index) = self.index(action) else {
+            return String::new();
+        };
+        let names: Vec<String> = self.actions[index]
+            .bindings
+            .iter()
+            .map(Binding::name)
+            .collect();
+        if names.is_empty() {
+            String::from("-")
+        } else {
+            names.join("/")
+        }
+    }
+}
@@ -0,0 +1,667 @@
+// Pruebas de propiedades de las intersecciones: rayos aleatorios (con semilla fija, para
+// que un fallo se pueda repetir) contra el resultado analítico calculado en f64. Cubren
+// orígenes dentro del objeto, rayos rasantes, rayos paralelos a los ejes y objetos lejos
+// del origen del mundo, y el sombreado de un rayo que sale desde dentro de un objeto.
+use crate::camera::Camera;
+use crate::color::Color;
+use crate::cuboid::Cuboid;
+use crate::framebuffer::Framebuffer;
+use crate::material::Material;
+use crate::ray_intersect::{Intersect, RayIntersect};
+use crate::render_mode::RenderMode;
+use crate::renderer::{Quality, RenderContext};
+use crate::scene::Scene;
+use crate::sphere::Sphere;
+use nalgebra_glm::Vec3;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+const CASES: usize = 2000;
+
+// Error aceptado en distancias y posiciones, relativo a la magnitud de las coordenadas
+// (unos 16 ulp de f32)
+const TOLERANCE: f64 = 2e-6;
+// Los rayos que pasan a menos de esto (en la misma escala) del borde no se clasifican
+const AMBIGUOUS_BAND: f64 = 5.0 * TOLERANCE;
+
+const FAR_MIN: f32 = 1e3;
+const FAR_MAX: f32 = 1e4;
+
+type Point = [f64; 3];
+
+#[derive(Debug)]
+enum Expected {
+    Hit { distance: f64, inside: bool },
+    Miss,
+    Ambiguous, // Rasante o con el origen sobre la superficie: cualquier respuesta vale
+}
+
+fn to_f64(vector: &Vec3) -> Point {
+    [vector.x as f64, vector.y as f64, vector.z as f64]
+}
+
+fn magnitude(point: &Point) -> f64 {
+    point.iter().fold(0.0, |max, value| value.abs().max(max))
+}
+
+fn random_unit(rng: &mut StdRng) -> Vec3 {
+    loop {
+        let vector = Vec3::new(
+            rng.gen_range(-1.0..1.0),
+            rng.gen_range(-1.0..1.0),
+            rng.gen_range(-1.0..1.0),
+        );
+        let length = vector.norm();
+        if length > 0.1 && length <= 1.0 {
+            return vector / length;
+        }
+    }
+}
+
+fn random_axis(rng: &mut StdRng) -> Vec3 {
+    let mut direction = Vec3::zeros();
+    direction[rng.gen_range(0..3)] = if rng.gen() { 1.0 } else { -1.0 };
+    direction
+}
+
+// Centro cerca del origen o, con `far`, con cada coordenada entre FAR_MIN y FAR_MAX
+fn random_center(rng: &mut StdRng, far: bool) -> Vec3 {
+    Vec3::from_fn(|_, _| {
+        if far {
+            let sign = if rng.gen() { 1.0 } else { -1.0 };
+            sign * rng.gen_range(FAR_MIN..FAR_MAX)
+        } else {
+            rng.gen_range(-5.0..5.0)
+        }
+    })
+}
+
+fn random_cuboid(rng: &mut StdRng, far: bool) -> Cuboid {
+    Cuboid::new(
+        random_center(rng, far),
+        rng.gen_range(0.5..4.0),
+        rng.gen_range(0.5..4.0),
+        rng.gen_range(0.5..4.0),
+        Material::black(),
+    )
+}
+
+fn random_sphere(rng: &mut StdRng, far: bool) -> Sphere {
+    Sphere::new(
+        random_center(rng, far),
+        rng.gen_range(0.5..3.0),
+        Material::black(),
+    )
+}
+
+// Punto dentro de la caja, a una fracción `fill` del tamaño alrededor del centro
+fn point_in_cuboid(rng: &mut StdRng, cuboid: &Cuboid, fill: f32) -> Vec3 {
+    let half = cuboid.size() / 2.0 * fill;
+    cuboid.center + Vec3::from_fn(|axis, _| rng.gen_range(-half[axis]..half[axis]))
+}
+
+fn point_in_sphere(rng: &mut StdRng, sphere: &Sphere, fill: f32) -> Vec3 {
+    sphere.center + random_unit(rng) * rng.gen_range(0.0..sphere.radius * fill)
+}
+
+// Primer t >= 0 en que el rayo toca la caja [min - grow, max + grow]
+fn box_distance(
+    origin: &Point,
+    direction: &Point,
+    min: &Point,
+    max: &Point,
+    grow: f64,
+) -> Option<f64> {
+    let mut near = f64::NEG_INFINITY;
+    let mut far = f64::INFINITY;
+    for axis in 0..3 {
+        let (low, high) = (min[axis] - grow, max[axis] + grow);
+        if direction[axis] == 0.0 {
+            if origin[axis] < low || origin[axis] > high {
+                return None;
+            }
+            continue;
+        }
+        let t0 = (low - origin[axis]) / direction[axis];
+        let t1 = (high - origin[axis]) / direction[axis];
+        near = near.max(t0.min(t1));
+        far = far.min(t0.max(t1));
+    }
+    if near > far || far < 0.0 {
+        None
+    } else if near >= 0.0 {
+        Some(near)
+    } else {
+        Some(far)
+    }
+}
+
+fn box_contains(point: &Point, min: &Point, max: &Point, grow: f64) -> bool {
+    (0..3).all(|axis| point[axis] >= min[axis] - grow && point[axis] <= max[axis] + grow)
+}
+
+fn expected_cuboid(cuboid: &Cuboid, origin: &Vec3, direction: &Vec3) -> Expected {
+    let half = to_f64(&(cuboid.size() / 2.0));
+    let center = to_f64(&cuboid.center);
+    let min = [
+        center[0] - half[0],
+        center[1] - half[1],
+        center[2] - half[2],
+    ];
+    let max = [
+        center[0] + half[0],
+        center[1] + half[1],
+        center[2] + half[2],
+    ];
+    let origin = to_f64(origin);
+    let direction = to_f64(direction);
+    let band = AMBIGUOUS_BAND * (1.0 + magnitude(&origin).max(magnitude(&center)));
+
+    let inside = box_contains(&origin, &min, &max, -band);
+    if inside != box_contains(&origin, &min, &max, band) {
+        return Expected::Ambiguous;
+    }
+    let shrunk = box_distance(&origin, &direction, &min, &max, -band);
+    let grown = box_distance(&origin, &direction, &min, &max, band);
+    match (shrunk, grown) {
+        (Some(_), _) => Expected::Hit {
+            distance: box_distance(&origin, &direction, &min, &max, 0.0).unwrap(),
+            inside,
+        },
+        (None, None) => Expected::Miss,
+        _ => Expected::Ambiguous,
+    }
+}
+
+// Primer t >= 0 en que el rayo toca la esfera de radio `radius`
+fn sphere_distance(origin: &Point, direction: &Point, center: &Point, radius: f64) -> Option<f64> {
+    let offset = [
+        origin[0] - center[0],
+        origin[1] - center[1],
+        origin[2] - center[2],
+    ];
+    let a = direction.iter().map(|value| value * value).sum::<f64>();
+    let b = 2.0
+        * (0..3)
+            .map(|axis| direction[axis] * offset[axis])
+            .sum::<f64>();
+    let c = offset.iter().map(|value| value * value).sum::<f64>() - radius * radius;
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let t0 = (-b - discriminant.sqrt()) / (2.0 * a);
+    let t1 = (-b + discriminant.sqrt()) / (2.0 * a);
+    if t0 >= 0.0 {
+        Some(t0)
+    } else if t1 >= 0.0 {
+        Some(t1)
+    } else {
+        None
+    }
+}
+
+fn expected_sphere(sphere: &Sphere, origin: &Vec3, direction: &Vec3) -> Expected {
+    let center = to_f64(&sphere.center);
+    let radius = sphere.radius as f64;
+    let origin = to_f64(origin);
+    let direction = to_f64(direction);
+    let band = AMBIGUOUS_BAND * (1.0 + magnitude(&origin).max(magnitude(&center)));
+
+    let distance_to_center = (0..3)
+        .map(|axis| (origin[axis] - center[axis]).powi(2))
+        .sum::<f64>()
+        .sqrt();
+    if (distance_to_center - radius).abs() <= band {
+        return Expected::Ambiguous;
+    }
+    let shrunk = sphere_distance(&origin, &direction, &center, radius - band);
+    let grown = sphere_distance(&origin, &direction, &center, radius + band);
+    match (shrunk, grown) {
+        (Some(_), _) => Expected::Hit {
+            distance: sphere_distance(&origin, &direction, &center, radius).unwrap(),
+            inside: distance_to_center < radius,
+        },
+        (None, None) => Expected::Miss,
+        _ => Expected::Ambiguous,
+    }
+}
+
+// Comprobaciones comunes a todas las primitivas; devuelve la tolerancia usada para que
+// cada forma verifique que el punto está sobre su superficie
+fn check_hit(
+    hit: &Intersect,
+    origin: &Vec3,
+    direction: &Vec3,
+    expected: &Expected,
+    case: &str,
+) -> Option<f64> {
+    let values = [
+        hit.point.x,
+        hit.point.y,
+        hit.point.z,
+        hit.normal.x,
+        hit.normal.y,
+        hit.normal.z,
+        hit.distance,
+        hit.u,
+        hit.v,
+    ];
+    assert!(
+        values.iter().all(|value| value.is_finite()),
+        "{}: valores no finitos {:?}",
+        case,
+        hit
+    );
+
+    let (distance, inside) = match expected {
+        Expected::Ambiguous => return None,
+        Expected::Miss => {
+            assert!(
+                !hit.is_intersecting,
+                "{}: se esperaba que no tocara, {:?}",
+                case, hit
+            );
+            return None;
+        }
+        Expected::Hit { distance, inside } => (*distance, *inside),
+    };
+    assert!(
+        hit.is_intersecting,
+        "{}: se esperaba un impacto a {}",
+        case, distance
+    );
+
+    let tolerance = TOLERANCE * (1.0 + magnitude(&to_f64(origin)) + distance);
+    assert!(
+        (hit.distance as f64 - distance).abs() <= tolerance,
+        "{}: distancia {} en lugar de {}",
+        case,
+        hit.distance,
+        distance
+    );
+    let along_ray = origin + direction * hit.distance;
+    assert!(
+        (hit.point - along_ray).norm() as f64 <= tolerance,
+        "{}: el punto {:?} no está sobre el rayo",
+        case,
+        hit.point
+    );
+    assert!(
+        (hit.normal.norm() - 1.0).abs() < 1e-4,
+        "{}: normal {:?} no unitaria",
+        case,
+        hit.normal
+    );
+
+    // La normal apunta hacia afuera: contra el rayo al entrar y con él al salir
+    let facing = hit.normal.dot(direction);
+    if inside {
+        assert!(
+            facing >= 0.0,
+            "{}: la normal de salida apunta hacia adentro",
+            case
+        );
+    } else {
+        assert!(
+            facing <= 0.0,
+            "{}: la normal de entrada apunta hacia adentro",
+            case
+        );
+    }
+    Some(tolerance)
+}
+
+fn check_cuboid(cuboid: &Cuboid, origin: &Vec3, direction: &Vec3, case: &str) -> Expected {
+    let hit = cuboid.ray_intersect(origin, direction);
+    let expected = expected_cuboid(cuboid, origin, direction);
+    let case = format!(
+        "{} (centro {:?}, tamaño {:?}, origen {:?}, dirección {:?})",
+        case,
+        cuboid.center,
+        cuboid.size(),
+        origin,
+        direction
+    );
+
+    if let Some(tolerance) = check_hit(&hit, origin, direction, &expected, &case) {
+        // Normal de una cara: un solo eje, y el punto está sobre el plano de esa cara
+        let axis = hit.normal.iamax();
+        assert_eq!(
+            hit.normal[axis].abs(),
+            1.0,
+            "{}: normal {:?}",
+            case,
+            hit.normal
+        );
+        assert_eq!(
+            hit.normal.abs().sum(),
+            1.0,
+            "{}: normal {:?}",
+            case,
+            hit.normal
+        );
+        let face = cuboid.center[axis] + hit.normal[axis] * cuboid.size()[axis] / 2.0;
+        assert!(
+            (hit.point[axis] - face).abs() as f64 <= tolerance,
+            "{}: el punto {:?} no está en la cara de la normal {:?}",
+            case,
+            hit.point,
+            hit.normal
+        );
+    }
+    expected
+}
+
+fn check_sphere(sphere: &Sphere, origin: &Vec3, direction: &Vec3, case: &str) -> Expected {
+    let hit = sphere.ray_intersect(origin, direction);
+    let expected = expected_sphere(sphere, origin, direction);
+    let case = format!(
+        "{} (centro {:?}, radio {}, origen {:?}, dirección {:?})",
+        case, sphere.center, sphere.radius, origin, direction
+    );
+
+    if let Some(tolerance) = check_hit(&hit, origin, direction, &expected, &case) {
+        let radial = hit.point - sphere.center;
+        assert!(
+            (radial.norm() - sphere.radius).abs() as f64 <= tolerance,
+            "{}: el punto {:?} no está sobre la esfera",
+            case,
+            hit.point
+        );
+        assert!(
+            (radial / sphere.radius - hit.normal).norm() as f64
+                <= 1e-4 + tolerance / sphere.radius as f64,
+            "{}: normal {:?} no radial",
+            case,
+            hit.normal
+        );
+    }
+    expected
+}
+
+fn assert_hit(expected: Expected, case: &str) {
+    assert!(
+        matches!(expected, Expected::Hit { .. }),
+        "{}: el caso debía ser un impacto claro, {:?}",
+        case,
+        expected
+    );
+}
+
+#[test]
+fn cuboid_random_rays() {
+    let mut rng = StdRng::seed_from_u64(1);
+    let (mut hits, mut misses) = (0, 0);
+    for _ in 0..CASES {
+        let cuboid = random_cuboid(&mut rng, false);
+        let origin = cuboid.center + random_unit(&mut rng) * rng.gen_range(0.0..8.0);
+        let direction = random_unit(&mut rng);
+        match check_cuboid(&cuboid, &origin, &direction, "rayo aleatorio") {
+            Expected::Hit { .. } => hits += 1,
+            Expected::Miss => misses += 1,
+            Expected::Ambiguous => {}
+        }
+    }
+    // Que la muestra tenga de los dos casos
+    assert!(
+        hits > CASES / 20 && misses > CASES / 20,
+        "{} {}",
+        hits,
+        misses
+    );
+}
+
+#[test]
+fn cuboid_rays_from_outside() {
+    let mut rng = StdRng::seed_from_u64(2);
+    for _ in 0..CASES {
+        let cuboid = random_cuboid(&mut rng, false);
+        let target = point_in_cuboid(&mut rng, &cuboid, 0.9);
+        let origin = cuboid.center + random_unit(&mut rng) * rng.gen_range(4.0..20.0);
+        let direction = (target - origin).normalize();
+        assert_hit(
+            check_cuboid(&cuboid, &origin, &direction, "desde afuera"),
+            "desde afuera",
+        );
+    }
+}
+
+#[test]
+fn cuboid_origin_inside() {
+    let mut rng = StdRng::seed_from_u64(3);
+    for _ in 0..CASES {
+        let cuboid = random_cuboid(&mut rng, false);
+        let origin = point_in_cuboid(&mut rng, &cuboid, 0.9);
+        let direction = random_unit(&mut rng);
+        assert_hit(
+            check_cuboid(&cuboid, &origin, &direction, "origen dentro"),
+            "origen dentro",
+        );
+    }
+}
+
+#[test]
+fn cuboid_origin_inside_refracts_through() {
+    // Con la cámara dentro de un cubo de vidrio, el rayo refractado tiene que salir por
+    // la cara de atrás y ver la pared en vez de rebotar dentro del cubo hasta el cielo
+    let glass = Material::new(
+        Color::new(255, 255, 255),
+        125.0,
+        [0.0, 0.0, 0.0, 1.0],
+        1.5,
+        None,
+        None,
+    );
+    let mut wall = Material::black();
+    wall.diffuse = Color::new(40, 200, 40);
+    wall.emission = 1.0;
+    let scene = Scene::new(
+        vec![
+            Box::new(Cuboid::new(Vec3::zeros(), 2.0, 2.0, 2.0, glass)),
+            Box::new(Cuboid::new(Vec3::new(0.0, 0.0, -4.0), 8.0, 8.0, 0.2, wall)),
+        ],
+        Vec::new(),
+    );
+    let camera = Camera::new(
+        Vec3::new(0.3, -0.2, 0.4),
+        Vec3::new(0.3, -0.2, -1.0),
+        Vec3::new(0.0, 1.0, 0.0),
+    );
+    let context = RenderContext {
+        scene: &scene,
+        camera: &camera,
+        width: 3,
+        height: 3,
+        time: 0.0,
+        quality: Quality::FULL,
+        mode: RenderMode::Beauty,
+        motion: None,
+        placements: &[],
+    };
+    let mut framebuffer = Framebuffer::new(3, 3);
+    context.render(&mut framebuffer);
+
+    let center = framebuffer.buffer[4];
+    assert_eq!(
+        (center.r, center.g, center.b),
+        (40, 200, 40),
+        "se ve {:?} en vez de la pared",
+        center
+    );
+}
+
+#[test]
+fn cuboid_axis_parallel_rays() {
+    let mut rng = StdRng::seed_from_u64(4);
+    for _ in 0..CASES {
+        let cuboid = random_cuboid(&mut rng, false);
+        let origin = point_in_cuboid(&mut rng, &cuboid, 3.0);
+        let direction = random_axis(&mut rng);
+        check_cuboid(&cuboid, &origin, &direction, "paralelo a un eje");
+    }
+
+    // Origen exactamente sobre el plano de una cara y rayo paralelo a ella: antes daba 0/0
+    let cuboid = Cuboid::new(Vec3::zeros(), 2.0, 2.0, 2.0, Material::black());
+    let origin = Vec3::new(-1.0, 0.0, 0.0);
+    let hit = cuboid.ray_intersect(&origin, &Vec3::new(0.0, 1.0, 0.0));
+    assert!(hit.is_intersecting && hit.distance.is_finite(), "{:?}", hit);
+    assert_eq!(hit.normal, Vec3::new(0.0, 1.0, 0.0));
+    assert!((hit.distance - 1.0).abs() < 1e-6);
+}
+
+#[test]
+fn cuboid_grazing_rays() {
+    // Rayos paralelos a una cara, apenas dentro o apenas fuera de su plano
+    let mut rng = StdRng::seed_from_u64(5);
+    for case in 0..CASES {
+        let cuboid = random_cuboid(&mut rng, false);
+        let size = cuboid.size();
+        let axis = rng.gen_range(0..3);
+        let side = if rng.gen() { 1.0 } else { -1.0 };
+        let offset = if case % 2 == 0 { -1e-3 } else { 1e-3 };
+
+        let mut target = point_in_cuboid(&mut rng, &cuboid, 0.9);
+        target[axis] = cuboid.center[axis] + side * (size[axis] / 2.0 + offset);
+        let mut direction = random_unit(&mut rng);
+        direction[axis] = 0.0;
+        if direction.norm() < 0.1 {
+            continue;
+        }
+        let direction = direction.normalize();
+        let origin = target - direction * 10.0;
+
+        let expected = check_cuboid(&cuboid, &origin, &direction, "rasante");
+        if offset < 0.0 {
+            assert_hit(expected, "rasante por dentro");
+        } else {
+            assert!(matches!(expected, Expected::Miss), "rasante por fuera");
+        }
+    }
+}
+
+#[test]
+fn cuboid_far_from_origin() {
+    let mut rng = StdRng::seed_from_u64(6);
+    for _ in 0..CASES {
+        let cuboid = random_cuboid(&mut rng, true);
+        let target = point_in_cuboid(&mut rng, &cuboid, 0.5);
+        let from_outside = cuboid.center + random_unit(&mut rng) * rng.gen_range(4.0..20.0);
+        let direction = (target - from_outside).normalize();
+        assert_hit(
+            check_cuboid(&cuboid, &from_outside, &direction, "lejos, desde afuera"),
+            "lejos, desde afuera",
+        );
+
+        let inside = point_in_cuboid(&mut rng, &cuboid, 0.5);
+        let direction = random_unit(&mut rng);
+        assert_hit(
+            check_cuboid(&cuboid, &inside, &direction, "lejos, origen dentro"),
+            "lejos, origen dentro",
+        );
+    }
+}
+
+#[test]
+fn sphere_random_rays() {
+    let mut rng = StdRng::seed_from_u64(7);
+    let (mut hits, mut misses) = (0, 0);
+    for _ in 0..CASES {
+        let sphere = random_sphere(&mut rng, false);
+        let origin = sphere.center + random_unit(&mut rng) * rng.gen_range(0.0..8.0);
+        let direction = random_unit(&mut rng);
+        match check_sphere(&sphere, &origin, &direction, "rayo aleatorio") {
+            Expected::Hit { .. } => hits += 1,
+            Expected::Miss => misses += 1,
+            Expected::Ambiguous => {}
+        }
+    }
+    assert!(
+        hits > CASES / 20 && misses > CASES / 20,
+        "{} {}",
+        hits,
+        misses
+    );
+}
+
+#[test]
+fn sphere_origin_inside() {
+    let mut rng = StdRng::seed_from_u64(8);
+    for _ in 0..CASES {
+        let sphere = random_sphere(&mut rng, false);
+        let origin = point_in_sphere(&mut rng, &sphere, 0.9);
+        let direction = random_unit(&mut rng);
+        assert_hit(
+            check_sphere(&sphere, &origin, &direction, "origen dentro"),
+            "origen dentro",
+        );
+    }
+}
+
+#[test]
+fn sphere_axis_parallel_rays() {
+    let mut rng = StdRng::seed_from_u64(9);
+    for _ in 0..CASES {
+        let sphere = random_sphere(&mut rng, false);
+        let origin = sphere.center + Vec3::from_fn(|_, _| rng.gen_range(-3.0..3.0) * sphere.radius);
+        let direction = random_axis(&mut rng);
+        check_sphere(&sphere, &origin, &direction, "paralelo a un eje");
+    }
+}
+
+#[test]
+fn sphere_grazing_rays() {
+    // Rayos que pasan a un radio del centro, apenas por dentro o apenas por fuera
+    let mut rng = StdRng::seed_from_u64(10);
+    for case in 0..CASES {
+        let sphere = random_sphere(&mut rng, false);
+        let direction = random_unit(&mut rng);
+        let mut side = random_unit(&mut rng);
+        side = (side - direction * side.dot(&direction)).normalize();
+        let scale = if case % 2 == 0 {
+            1.0 - 1e-3
+        } else {
+            1.0 + 1e-3
+        };
+        let closest = sphere.center + side * sphere.radius * scale;
+        let origin = closest - direction * 10.0;
+
+        let expected = check_sphere(&sphere, &origin, &direction, "rasante");
+        if scale < 1.0 {
+            assert_hit(expected, "rasante por dentro");
+        } else {
+            assert!(matches!(expected, Expected::Miss), "rasante por fuera");
+        }
+    }
+}
+
+#[test]
+fn sphere_far_from_origin() {
+    let mut rng = StdRng::seed_from_u64(11);
+    for _ in 0..CASES {
+        let sphere = random_sphere(&mut rng, true);
+        let target = point_in_sphere(&mut rng, &sphere, 0.5);
+        let near_origin = sphere.center + random_unit(&mut rng) * rng.gen_range(4.0..20.0);
+        let direction = (target - near_origin).normalize();
+        assert_hit(
+            check_sphere(&sphere, &near_origin, &direction, "lejos, desde cerca"),
+            "lejos, desde cerca",
+        );
+
+        // Rayos largos: el origen a miles de unidades de la esfera
+        let distant_origin =
+            sphere.center + random_unit(&mut rng) * rng.gen_range(FAR_MIN..FAR_MAX);
+        let direction = (target - distant_origin).normalize();
+        assert_hit(
+            check_sphere(&sphere, &distant_origin, &direction, "lejos, rayo largo"),
+            "lejos, rayo largo",
+        );
+
+        let inside = point_in_sphere(&mut rng, &sphere, 0.5);
+        let direction = random_unit(&mut rng);
+        assert_hit(
+            check_sphere(&sphere, &inside, &direction, "lejos, origen dentro"),
+            "lejos, origen dentro",
+        );
+    }
+}
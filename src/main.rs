@@ -1,701 +1,1265 @@
+mod animation;
+mod bench;
+mod bookmarks;
 mod camera;
+mod camera_path;
 mod color;
 mod cuboid;
+mod denoise;
+mod fog;
 mod framebuffer;
+mod input;
+#[cfg(test)]
+mod intersection_tests;
 mod light;
 mod material;
+mod overlay;
+mod passes;
 mod ray_intersect;
+#[cfg(test)]
+mod reference_tests;
+mod render_mode;
+mod renderer;
+mod sampling;
+mod scene;
+#[cfg(test)]
+mod scene_tests;
+mod sphere;
+mod text_format;
 mod texture; // Importa tu nuevo módulo
+mod tiles;
 
 use crate::color::Color;
 use crate::framebuffer::Framebuffer;
-use crate::ray_intersect::{Intersect, RayIntersect};
-use crate::texture::Texture;
-use camera::Camera;
-use cuboid::Cuboid;
-use light::Light;
-use material::Material;
-use minifb::{Window, WindowOptions};
-use nalgebra::ComplexField;
+use animation::Timeline;
+use bookmarks::{Bookmark, Bookmarks, BOOKMARK_SLOTS};
+use camera::{Camera, CameraMode, Projection};
+use camera_path::{CameraKeyframe, CameraPath};
+use denoise::{DenoiseFilter, Denoiser};
+use fog::{Fog, Volume};
+use input::{Action, Input};
+use minifb::{MouseMode, Window, WindowOptions};
 use nalgebra_glm::Vec3;
-use once_cell::sync::Lazy;
-use rayon::prelude::*;
-use std::sync::Arc; // Importa tu nuevo módulo
-use std::time::Instant;
-
-static LADRILLOS: Lazy<Arc<Texture>> = Lazy::new(|| Arc::new(Texture::new("assets/ladrillos.png")));
-static WATER: Lazy<Arc<Texture>> = Lazy::new(|| Arc::new(Texture::new("assets/WATER.jpg")));
-static LADRILLOS_NEGROS: Lazy<Arc<Texture>> =
-    Lazy::new(|| Arc::new(Texture::new("assets/ladrillos_negros.png")));
-static SUELO: Lazy<Arc<Texture>> = Lazy::new(|| Arc::new(Texture::new("assets/suelo.png")));
-static BRICKS: Lazy<Arc<Texture>> = Lazy::new(|| Arc::new(Texture::new("assets/Bricks.png")));
-static LAVA: Lazy<Arc<Texture>> = Lazy::new(|| Arc::new(Texture::new("assets/Lava.jpg")));
-
-fn reflect(incident: &Vec3, normal: &Vec3) -> Vec3 {
-    incident - 2.0 * incident.dot(normal) * normal
+use overlay::{highlight, Overlay};
+use ray_intersect::RayIntersect;
+use render_mode::RenderMode;
+use renderer::{render, Motion, Quality, RayStats, RenderContext};
+use sampling::AdaptiveSampling;
+use scene::Scene;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::process;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tiles::{RenderJob, TileOrder};
+
+// Tamaño de los panoramas equirectangulares exportados (proporción 2:1)
+const PANORAMA_WIDTH: usize = 2048;
+const PANORAMA_HEIGHT: usize = 1024;
+
+// Velocidades del modo de vuelo libre (unidades y radianes por segundo)
+const FLY_SPEED: f32 = 2.0;
+const LOOK_SPEED: f32 = 1.5;
+const MOUSE_SENSITIVITY: f32 = 0.005; // Radianes por píxel
+
+// Archivo donde el visor graba el recorrido de cámara
+const CAMERA_PATH_FILE: &str = "camera_path.txt";
+// Archivo con los marcadores de cámara del visor, junto al de la escena
+const BOOKMARKS_FILE: &str = "bookmarks.txt";
+// Controles del visor; si no existe se usan los de `Input::default`
+const INPUT_FILE: &str = "controls.txt";
+// Archivo donde el modo edición guarda la escena si no se indicó `--scene`
+const SCENE_FILE: &str = "scene.txt";
+// Fuente para los textos superpuestos del visor
+const FONT_FILE: &str = "assets/DejaVuSansMono.ttf";
+const HIGHLIGHT_COLOR: Color = Color {
+    r: 255,
+    g: 200,
+    b: 0,
+};
+const OVERLAY_MARGIN: usize = 10;
+// Peso del último cuadro en los FPS promediados del HUD
+const FPS_SMOOTHING: f32 = 0.1;
+
+// Acciones y su descripción para el panel de ayuda; las teclas salen de los controles
+const HELP: [(&[Action], &str); 33] = [
+    (
+        &[
+            Action::OrbitLeft,
+            Action::OrbitRight,
+            Action::OrbitUp,
+            Action::OrbitDown,
+        ],
+        "rotar la cámara",
+    ),
+    (&[Action::ZoomIn, Action::ZoomOut], "acercar o alejar"),
+    (&[Action::SunBack, Action::SunForward], "girar el sol"),
+    (
+        &[
+            Action::FlyForward,
+            Action::FlyBack,
+            Action::FlyLeft,
+            Action::FlyRight,
+        ],
+        "moverse en vuelo libre",
+    ),
+    (
+        &[Action::FlyUp, Action::FlyDown],
+        "subir o bajar en vuelo libre",
+    ),
+    (
+        &[Action::FlyFast, Action::FlySlow],
+        "más rápido o más lento en vuelo libre",
+    ),
+    (
+        &[
+            Action::LookLeft,
+            Action::LookRight,
+            Action::LookUp,
+            Action::LookDown,
+        ],
+        "mirar en vuelo libre",
+    ),
+    (&[Action::MouseLook], "arrastrar para mirar en vuelo libre"),
+    (
+        &[Action::FlySunBack, Action::FlySunForward],
+        "girar el sol en vuelo libre",
+    ),
+    (&[Action::ToggleCameraMode], "cámara orbital o vuelo libre"),
+    (
+        &[Action::Select],
+        "seleccionar y enfocar (colocar bloque al editar)",
+    ),
+    (&[Action::ToggleEdit], "modo edición"),
+    (&[Action::RemoveBlock], "quitar bloque al editar"),
+    (&[Action::PaintBlock], "pintar bloque al editar"),
+    (&[Action::SaveScene], "guardar la escena editada"),
+    (&[Action::FovIn, Action::FovOut], "campo de visión"),
+    (
+        &[Action::ApertureClose, Action::ApertureOpen],
+        "apertura del lente",
+    ),
+    (&[Action::NextBokeh], "forma del bokeh"),
+    (&[Action::NextProjection], "proyección"),
+    (
+        &[Action::RecordKeyframe, Action::TogglePlayback],
+        "grabar keyframe / reproducir recorrido",
+    ),
+    (
+        &[Action::Slot(0), Action::Slot(9)],
+        "marcador (material al editar)",
+    ),
+    (&[Action::SaveModifier], "con un marcador, guardar la vista"),
+    (&[Action::ToggleTimeline], "línea de tiempo"),
+    (&[Action::NextRenderMode], "modo de render"),
+    (&[Action::TogglePreview], "vista previa a baja resolución"),
+    (&[Action::ToggleAdaptive], "muestreo adaptativo"),
+    (&[Action::NextDenoiser], "filtro de ruido"),
+    (
+        &[Action::ToggleFog, Action::ToggleVolumetrics],
+        "niebla / rayos volumétricos",
+    ),
+    (&[Action::Capture], "captura PNG"),
+    (
+        &[Action::CaptureAovs],
+        "captura de las vistas de depuración",
+    ),
+    (&[Action::CapturePasses], "captura de los pases en EXR"),
+    (&[Action::ToggleHelp], "mostrar u ocultar esta ayuda"),
+    (&[Action::Quit], "salir"),
+];
+
+// Segundos entre keyframes grabados desde el visor
+const KEYFRAME_SPACING: f32 = 2.0;
+
+// Mosaicos del visor: se renderizan desde el centro y se muestran a medida que terminan
+const VIEWER_TILE_SIZE: usize = 32;
+const VIEWER_TILE_ORDER: TileOrder = TileOrder::Spiral;
+// Tiempo de render por iteración del bucle antes de mostrar el avance y leer la entrada
+const FRAME_BUDGET: Duration = Duration::from_millis(50);
+// Divisor de la resolución de la vista previa mientras la cámara o la luz se mueven
+const PREVIEW_SCALE: usize = 4;
+// Píxeles del framebuffer por píxel de la ventana si no se indica `--render-scale`;
+// minifb reduce la imagen al mostrarla. minifb no informa la escala de la pantalla
+const DEFAULT_RENDER_SCALE: f32 = 1.3;
+
+// Opciones para renderizar un recorrido de cámara sin ventana
+struct HeadlessOptions {
+    path_file: String,
+    fps: f32,
+    output_dir: String,
+    shutter: f32, // Fracción del cuadro con el obturador abierto (0 = sin desenfoque)
+    shutter_samples: u32, // Rayos por píxel, cada uno en un instante del obturador
+    quality: Quality,
+    denoiser: Option<Denoiser>,
+    aovs: Vec<RenderMode>, // Vistas extra guardadas junto a cada cuadro
+    passes: Option<PassFormat>,
 }
 
-fn cast_ray(
-    ray_origin: &Vec3,
-    ray_direction: &Vec3,
-    objects: &[Box<dyn RayIntersect>],
-    lights: &[Light], // Cambiar a un vector de luces
-    depth: u32,
-    use_normal_map: bool, // Añade un parámetro para controlar si se usa el mapeo de normales
-    time: f32,            // Añadimos el tiempo para animar texturas
-) -> Color {
-    if depth > 3 {
-        return Color::new(0, 90, 150); // Color de fondo o "skybox"
-    }
-
-    let mut closest_intersect = Intersect::empty();
-    let mut zbuffer = f32::INFINITY;
-
-    for object in objects {
-        let tmp = object.ray_intersect(ray_origin, ray_direction);
-        if tmp.is_intersecting && tmp.distance < zbuffer {
-            zbuffer = tmp.distance;
-            closest_intersect = tmp;
-        }
-    }
+// Cómo se guardan los pases para composición
+#[derive(Clone, Copy)]
+enum PassFormat {
+    Exr, // Un EXR de varias capas por cuadro
+    Png, // Un PNG de 16 bits por pase
+}
 
-    if !closest_intersect.is_intersecting {
-        return Color::new(0, 90, 150);
+// `--headless [--path archivo] [--fps n] [--out carpeta] [--shutter f] [--shutter-samples n]
+// [--adaptive] [--adaptive-threshold f] [--denoise atrous|bilateral] [--aovs all|modo,modo...]
+// [--passes exr|png]`; None si no se pidió
+fn parse_headless_options(args: &[String]) -> Option<HeadlessOptions> {
+    if !args.iter().any(|arg| arg == "--headless") {
+        return None;
     }
 
-    let bias = 0.01;
-    closest_intersect.point += closest_intersect.normal * bias;
-
-    let normal = closest_intersect.normal;
-
-    let diffuse_color = if use_normal_map {
-        let r = ((normal.x + 1.0) * 0.5 * 255.0) as i32;
-        let g = ((normal.y + 1.0) * 0.5 * 255.0) as i32;
-        let b = ((normal.z + 1.0) * 0.5 * 255.0) as i32;
-        Color::new(r, g, b)
-    } else {
-        closest_intersect
-            .material
-            .get_diffuse_color(closest_intersect.u, closest_intersect.v, time)
+    let mut options = HeadlessOptions {
+        path_file: CAMERA_PATH_FILE.to_string(),
+        fps: 24.0,
+        output_dir: "frames".to_string(),
+        shutter: 0.0,
+        shutter_samples: 8,
+        quality: Quality::FULL,
+        denoiser: None,
+        aovs: Vec::new(),
+        passes: None,
     };
 
-    let mut final_color = Color::new(0, 0, 0);
-
-    // Iterar sobre todas las fuentes de luz
-    for light in lights {
-        let shadow_intensity = cast_shadow(&closest_intersect, light, objects);
-        let light_intensity = light.intensity * (1.0 - shadow_intensity);
-
-        let light_dir = (light.position - closest_intersect.point).normalize();
-
-        let diffuse_intensity = normal.dot(&light_dir).max(0.0).min(1.0);
-        let diffuse = diffuse_color
-            * closest_intersect.material.albedo[0]
-            * diffuse_intensity
-            * light_intensity;
-
-        let view_dir = (ray_origin - closest_intersect.point).normalize();
-        let reflect_dir = reflect(&-light_dir, &normal);
-
-        let specular_intensity = view_dir
-            .dot(&reflect_dir)
-            .max(0.0)
-            .powf(closest_intersect.material.specular);
-        let specular = light.color
-            * closest_intersect.material.albedo[1]
-            * specular_intensity
-            * light_intensity;
-
-        final_color += diffuse + specular;
-    }
-
-    let reflectivity = closest_intersect.material.albedo[2];
-    let transparency = closest_intersect.material.albedo[3];
-
-    if reflectivity > 0.0 {
-        let reflect_dir = reflect(&ray_direction, &normal).normalize();
-        let reflect_origin = closest_intersect.point + normal * bias;
-        let reflect_color = cast_ray(
-            &reflect_origin,
-            &reflect_dir,
-            objects,
-            lights,
-            depth + 1,
-            use_normal_map,
-            time,
-        );
-        final_color += reflect_color * reflectivity;
-    }
-
-    if transparency > 0.0 {
-        let refract_dir = refract(
-            &ray_direction,
-            &normal,
-            closest_intersect.material.refractive_index,
-        );
-        let refract_origin = closest_intersect.point - normal * bias;
-        let refract_color = cast_ray(
-            &refract_origin,
-            &refract_dir,
-            objects,
-            lights,
-            depth + 1,
-            use_normal_map,
-            time,
-        );
-        final_color += refract_color * transparency;
+    let mut iter = args.iter().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--headless" => {}
+            "--scene" | "--timeline" | "--render-scale" => {
+                expect_value(&mut iter, arg); // Los lee `main` antes de elegir el modo
+            }
+            "--path" => options.path_file = expect_value(&mut iter, arg),
+            "--out" => options.output_dir = expect_value(&mut iter, arg),
+            "--fps" => {
+                // Con 0 o menos los tiempos de cada cuadro serían infinitos o NaN
+                options.fps = expect_number(&mut iter, arg);
+                if !(options.fps > 0.0 && options.fps.is_finite()) {
+                    eprintln!("--fps debe ser un número mayor que 0");
+                    process::exit(1);
+                }
+            }
+            "--shutter" => options.shutter = expect_number::<f32>(&mut iter, arg).clamp(0.0, 1.0),
+            "--shutter-samples" => {
+                options.shutter_samples = expect_number::<u32>(&mut iter, arg).max(1)
+            }
+            "--adaptive" => {
+                options.quality.adaptive = Some(AdaptiveSampling::default());
+            }
+            "--adaptive-threshold" => {
+                let threshold = expect_number(&mut iter, arg);
+                let sampling = options
+                    .quality
+                    .adaptive
+                    .get_or_insert_with(Default::default);
+                sampling.threshold = threshold;
+            }
+            "--denoise" => {
+                let name = expect_value(&mut iter, arg);
+                let filter = DenoiseFilter::from_name(&name).unwrap_or_else(|| {
+                    eprintln!("Filtro desconocido: {} (usa atrous o bilateral)", name);
+                    process::exit(1);
+                });
+                options.denoiser = Some(Denoiser::new(filter));
+            }
+            "--aovs" => {
+                let names = expect_value(&mut iter, arg);
+                options.aovs = if names == "all" {
+                    RenderMode::ALL[1..].to_vec()
+                } else {
+                    names
+                        .split(',')
+                        .map(|name| {
+                            RenderMode::from_name(name).unwrap_or_else(|| {
+                                eprintln!("Modo de render desconocido: {}", name);
+                                process::exit(1);
+                            })
+                        })
+                        .collect()
+                };
+            }
+            "--passes" => {
+                options.passes = match expect_value(&mut iter, arg).as_str() {
+                    "exr" => Some(PassFormat::Exr),
+                    "png" => Some(PassFormat::Png),
+                    other => {
+                        eprintln!("Formato de pases desconocido: {} (usa exr o png)", other);
+                        process::exit(1);
+                    }
+                };
+            }
+            other => {
+                eprintln!("Argumento desconocido: {}", other);
+                process::exit(1);
+            }
+        }
     }
 
-    final_color
+    Some(options)
 }
 
-fn render(
-    framebuffer: &mut Framebuffer,
-    objects: &[Box<dyn RayIntersect>],
-    camera: &Camera,
-    lights: &[Light], // Cambiar a un vector de luces
-    use_normal_map: bool,
-    time: f32, // Añadimos el tiempo para la animación
-) {
-    let width = framebuffer.width;
-    let height = framebuffer.height;
-    let aspect_ratio = width as f32 / height as f32;
-
-    framebuffer
-        .buffer
-        .par_chunks_mut(width)
-        .enumerate()
-        .for_each(|(y, row)| {
-            let screen_y = -(2.0 * y as f32) / height as f32 + 1.0;
-            for x in 0..width {
-                let screen_x = (2.0 * x as f32) / width as f32 - 1.0;
-                let screen_x = screen_x * aspect_ratio;
-
-                let world_direction = Vec3::new(screen_x, screen_y, -1.0);
-                let ray_direction = camera.basis_change(&world_direction);
-
-                let pixel_color = cast_ray(
-                    &camera.eye,
-                    &ray_direction,
-                    objects,
-                    lights,
-                    0,
-                    use_normal_map,
-                    time, // Pasamos el tiempo
-                );
-                row[x] = pixel_color;
-            }
-        });
+// Archivo `name` en la misma carpeta que `file`
+fn sibling_file(file: &str, name: &str) -> String {
+    Path::new(file)
+        .with_file_name(name)
+        .to_string_lossy()
+        .into_owned()
 }
 
-fn cast_shadow(intersect: &Intersect, light: &Light, objects: &[Box<dyn RayIntersect>]) -> f32 {
-    let light_dir = (light.position - intersect.point).normalize();
-    let bias = 0.001; // Use the same bias value as in cast_ray
-    let shadow_ray_origin = intersect.point + intersect.normal * bias;
-    let mut shadow_intensity = 0.0;
-    let light_distance = (light.position - shadow_ray_origin).magnitude();
-
-    for object in objects {
-        let shadow_intersect = object.ray_intersect(&shadow_ray_origin, &light_dir);
-        if shadow_intersect.is_intersecting {
-            let object_distance = shadow_intersect.distance;
-
-            // Calculamos la intensidad de la sombra basado en la distancia
-            shadow_intensity = 1.0 - (object_distance / light_distance).min(1.0);
-            break;
-        }
-    }
+// Valor que sigue a `flag` en la línea de comandos, si está
+fn option_value(args: &[String], flag: &str) -> Option<String> {
+    let position = args.iter().position(|arg| arg == flag)?;
+    args.get(position + 1).cloned()
+}
 
-    shadow_intensity
+fn expect_value<'a>(iter: &mut impl Iterator<Item = &'a String>, flag: &str) -> String {
+    iter.next().cloned().unwrap_or_else(|| {
+        eprintln!("Falta el valor de {}", flag);
+        process::exit(1);
+    })
 }
 
-fn refract(incident: &Vec3, normal: &Vec3, eta_t: f32) -> Vec3 {
-    let cosi = -incident.dot(normal).max(-1.0).min(1.0);
+fn expect_number<'a, T: std::str::FromStr>(
+    iter: &mut impl Iterator<Item = &'a String>,
+    flag: &str,
+) -> T {
+    expect_value(iter, flag).parse().unwrap_or_else(|_| {
+        eprintln!("{} debe ser un número", flag);
+        process::exit(1);
+    })
+}
 
-    let (n_cosi, eta, n_normal);
+// Renderiza el recorrido cuadro por cuadro como `frame_0001.png`, `frame_0002.png`...
+fn render_sequence(
+    options: &HeadlessOptions,
+    framebuffer: &mut Framebuffer,
+    scene: &mut Scene,
+    camera: &mut Camera,
+) -> Result<(), String> {
+    let path = CameraPath::load(&options.path_file)
+        .map_err(|e| format!("No se pudo leer {}: {}", options.path_file, e))?;
+    if path.is_empty() {
+        return Err(format!("{} no tiene keyframes", options.path_file));
+    }
+    fs::create_dir_all(&options.output_dir)
+        .map_err(|e| format!("No se pudo crear {}: {}", options.output_dir, e))?;
+
+    let duration = path.duration().max(scene.timeline.duration());
+    let frame_count = (duration * options.fps).floor() as usize + 1;
+    for frame in 0..frame_count {
+        let time = frame as f32 / options.fps;
+        path.apply(camera, time);
+        scene.animate(time);
+        // Los materiales y las luces quedan como al abrir el obturador; la cámara y los
+        // objetos animados se evalúan en el instante de cada rayo
+        let motion = (options.shutter > 0.0).then_some(Motion {
+            shutter: options.shutter / options.fps,
+            samples: options.shutter_samples,
+            path: Some(&path),
+        });
+        let context = RenderContext {
+            scene,
+            camera,
+            width: framebuffer.width,
+            height: framebuffer.height,
+            time,
+            quality: options.quality,
+            mode: RenderMode::Beauty,
+            motion,
+            placements: &[],
+        };
+        context.render(framebuffer);
+
+        // Las superficies que guían el filtro y las vistas extra se toman al inicio del cuadro
+        let context = RenderContext {
+            motion: None,
+            ..context
+        };
+        if let Some(denoiser) = &options.denoiser {
+            denoiser.apply(framebuffer, &context.render_features());
+        }
+        let prefix = format!("{}/frame_{:04}", options.output_dir, frame + 1);
+        save_aovs(&context, &options.aovs, &prefix)?;
+        match options.passes {
+            Some(PassFormat::Exr) => context
+                .render_passes()
+                .save_exr(&format!("{}.exr", prefix))?,
+            Some(PassFormat::Png) => context.render_passes().save_pngs(&prefix)?,
+            None => {}
+        }
 
-    if cosi < 0.0 {
-        // El rayo está entrando en el objeto
-        n_cosi = -cosi;
-        eta = 1.0 / eta_t;
-        n_normal = -normal;
-    } else {
-        // El rayo está saliendo del objeto
-        n_cosi = cosi;
-        eta = eta_t;
-        n_normal = *normal;
+        let file_path = format!("{}/frame_{:04}.png", options.output_dir, frame + 1);
+        framebuffer
+            .save(&file_path)
+            .map_err(|e| format!("No se pudo guardar {}: {}", file_path, e))?;
+        println!("Cuadro {}/{} -> {}", frame + 1, frame_count, file_path);
     }
 
-    let k = 1.0 - eta * eta * (1.0 - n_cosi * n_cosi);
+    Ok(())
+}
 
-    if k < 0.0 {
-        // Reflexión interna total
-        reflect(incident, &n_normal)
-    } else {
-        eta * incident + (eta * n_cosi - k.sqrt()) * n_normal
+// Renderiza y guarda una imagen por modo como `<prefijo>_<modo>.png`
+fn save_aovs(context: &RenderContext, modes: &[RenderMode], prefix: &str) -> Result<(), String> {
+    let mut framebuffer = Framebuffer::new(context.width, context.height);
+    for &mode in modes {
+        RenderContext { mode, ..*context }.render(&mut framebuffer);
+        let file_path = format!("{}_{}.png", prefix, mode.name());
+        framebuffer
+            .save(&file_path)
+            .map_err(|e| format!("No se pudo guardar {}: {}", file_path, e))?;
     }
+    Ok(())
 }
 
 fn main() {
     let start_time = Instant::now(); // Inicia el temporizador
 
-    let mut camera = Camera {
-        eye: Vec3::new(0.0, 0.0, 5.0),    // Posición de la cámara
-        center: Vec3::new(0.0, 0.0, 0.0), // Punto hacia el que está viendo la cámara
-        up: Vec3::new(0.0, 8.0, 0.0),     // Vector "arriba" de la cámara
-    };
-
-    // Definir múltiples luces
-    let light1 = Light::new(Vec3::new(5.0, 10.0, 5.0), Color::new(255, 255, 255), 2.0);
-
-    let light2 = Light::new(
-        Vec3::new(5.0, 10.0, -5.0),
-        Color::new(255, 100, 100), // Luz rojiza
-        1.0,
+    let mut camera = Camera::new(
+        Vec3::new(0.0, 0.0, 5.0), // Posición de la cámara
+        Vec3::new(0.0, 0.0, 0.0), // Punto hacia el que está viendo la cámara
+        Vec3::new(0.0, 8.0, 0.0), // Vector "arriba" de la cámara
     );
 
-    let mut lights = vec![light1];
+    let mut scene = scene::diorama();
 
-    let material_con_textura = Material::new(
-        Color::new(255, 255, 255),
-        500.0,
-        [0.8, 0.2, 0.0, 0.0],
-        1.5,
-        Some(LADRILLOS.clone()),
-        None,
-    );
-
-    let water = Material::new(
-        Color::new(200, 200, 255),
-        125.0,
-        [0.0, 0.5, 0.7, 0.5], // Difusa, especular, reflejo, transparencia
-        1.33,                 // Índice de refracción del agua
-        Some(WATER.clone()),
-        Some((50.0, 50.0)),
-    );
-
-    let lava = Material::new(
-        Color::new(255, 100, 0),
-        500.0,
-        [0.8, 0.2, 0.0, 0.0], // Difusa, especular, reflejo, transparencia
-        1.5,
-        Some(LAVA.clone()),
-        Some((25.0, 50.0)),
-    );
-
-    let ladrillos_neg = Material::new(
-        Color::new(255, 255, 255),
-        500.0,
-        [0.8, 0.2, 0.0, 0.1], // Difusa, especular, reflejo, transparencia
-        1.5,
-        Some(LADRILLOS_NEGROS.clone()),
-        None,
-    );
+    // Neblina cálida para el atardecer y medio para los rayos de luz entre las columnas
+    let sunset_fog = Fog::new(Color::new(200, 170, 150), 0.02, 0.3, 1.5, -0.5);
+    let light_shafts = Volume::new(0.08, 0.6, 24, 15.0);
 
-    let suelo = Material::new(
-        Color::new(128, 128, 128), // Color gris para el suelo
-        100.0,                     // Menor reflectividad que ladrillos_neg
-        [0.6, 0.3, 0.1, 0.0],      // Coeficientes de reflexión diferentes
-        1.0,                       // Índice de refracción diferente
-        Some(SUELO.clone()),       // Textura del suelo
-        None,
-    );
-
-    let texture_bricks = Material::new(
-        Color::new(255, 255, 255), // Color blanco para los ladrillos
-        250.0,                     // Alta reflectividad
-        [0.9, 0.3, 0.0, 0.0],      // Coeficientes de reflexión
-        1.0,                       // Índice de refracción
-        Some(BRICKS.clone()),      // Textura de los ladrillos
-        None,
-    );
+    let mut angle = 1.0; // Ángulo para el movimiento de la luz
 
-    // Agua compuesta por 4 cubos
-    let cuboid1 = Cuboid::new(
-        Vec3::new(-1.0, 0.0, 0.0), // Centro del cubo
-        1.0,                       // Ancho del cubo
-        1.0,                       // Altura del cubo
-        1.0,                       // Profundidad del cubo
-        water.clone(),             // Material de caucho
-    );
+    let mut render_mode = RenderMode::Beauty;
 
-    let cuboid2 = Cuboid::new(
-        Vec3::new(-2.0, 0.0, 0.0), // Centro del cubo
-        1.0,                       // Ancho del cubo
-        1.0,                       // Altura del cubo
-        1.0,                       // Profundidad del cubo
-        water.clone(),             // Material de caucho
-    );
+    let mut width = 1300; // Reduce el tamaño a la mitad
+    let mut height = 900;
+    let mut framebuffer = Framebuffer::new(width, height);
+    camera.aspect_ratio = width as f32 / height as f32;
 
-    let cuboid3 = Cuboid::new(
-        Vec3::new(-1.0, 0.0, 1.0), // Centro del cubo
-        1.0,                       // Ancho del cubo
-        1.0,                       // Altura del cubo
-        1.0,                       // Profundidad del cubo
-        water.clone(),             // Material de caucho
-    );
+    let args: Vec<String> = env::args().collect();
+    // Escenas de referencia con cámaras fijas; una línea JSON por escena en la salida
+    if args.iter().any(|arg| arg == "--bench") {
+        for result in bench::run() {
+            println!("{}", result.to_json());
+        }
+        return;
+    }
 
-    let cuboid4 = Cuboid::new(
-        Vec3::new(-2.0, 0.0, 1.0), // Centro del cubo
-        1.0,                       // Ancho del cubo
-        1.0,                       // Altura del cubo
-        1.0,                       // Profundidad del cubo
-        water.clone(),             // Material de caucho
-    );
+    let scene_file = option_value(&args, "--scene");
+    if let Some(file) = &scene_file {
+        match scene.load_objects(file) {
+            Ok(0) => {}
+            Ok(dropped) => eprintln!(
+                "Aviso: se descartaron {} animaciones que apuntaban a los objetos anteriores",
+                dropped
+            ),
+            Err(e) => {
+                eprintln!("No se pudo cargar {}: {}", file, e);
+                process::exit(1);
+            }
+        }
+    }
+    // Animaciones opcionales de objetos, materiales y luces (la escena no trae ninguna)
+    if let Some(file) = option_value(&args, "--timeline") {
+        scene.timeline = Timeline::load(&file).unwrap_or_else(|e| {
+            eprintln!("No se pudo cargar {}: {}", file, e);
+            process::exit(1);
+        });
+    }
+    if let Some(options) = parse_headless_options(&args) {
+        scene.set_sun_angle(angle);
+        if let Err(e) = render_sequence(&options, &mut framebuffer, &mut scene, &mut camera) {
+            eprintln!("{}", e);
+            process::exit(1);
+        }
+        return;
+    }
 
-    // Columnas de ladrillos
-    let cuboid5 = Cuboid::new(
-        Vec3::new(0.0, 0.0, -1.0), // Centro del cubo
-        1.0,                       // Ancho del cubo
-        1.0,                       // Altura del cubo
-        1.0,                       // Profundidad del cubo
-        ladrillos_neg.clone(),     // Material de caucho
-    );
+    // Si el recorrido existe pero no se puede leer, no se graba encima para no perderlo
+    let (mut camera_path, camera_path_file) = match CameraPath::load(CAMERA_PATH_FILE) {
+        Ok(path) => (path, Some(CAMERA_PATH_FILE)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            (CameraPath::new(), Some(CAMERA_PATH_FILE))
+        }
+        Err(e) => {
+            eprintln!(
+                "No se pudo leer {}: {}; los keyframes nuevos no se guardarán",
+                CAMERA_PATH_FILE, e
+            );
+            (CameraPath::new(), None)
+        }
+    };
+    let mut playback_start: Option<Instant> = None;
+    let mut animation_start: Option<Instant> = None;
+    scene.animate(0.0);
+    let bookmarks_file = sibling_file(scene_file.as_deref().unwrap_or(SCENE_FILE), BOOKMARKS_FILE);
+    // Lo mismo con los marcadores: guardar uno reescribiría todos los del archivo
+    let (mut bookmarks, bookmarks_file) = match Bookmarks::load(&bookmarks_file) {
+        Ok(bookmarks) => (bookmarks, Some(bookmarks_file)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {
+            (Bookmarks::default(), Some(bookmarks_file))
+        }
+        Err(e) => {
+            eprintln!(
+                "No se pudo leer {}: {}; los marcadores nuevos no se guardarán",
+                bookmarks_file, e
+            );
+            (Bookmarks::default(), None)
+        }
+    };
 
-    let cuboid6: Cuboid = Cuboid::new(
-        Vec3::new(0.0, 1.0, -1.0), // Centro del cubo
-        1.0,                       // Ancho del cubo
-        1.0,                       // Altura del cubo
-        1.0,                       // Profundidad del cubo
-        ladrillos_neg.clone(),     // Material de caucho
-    );
+    // En pantallas de alta densidad conviene 1 o menos para no renderizar de más
+    let render_scale = match option_value(&args, "--render-scale") {
+        None => DEFAULT_RENDER_SCALE,
+        Some(value) => match value.parse::<f32>() {
+            Ok(scale) if scale > 0.0 && scale.is_finite() => scale,
+            _ => {
+                eprintln!("--render-scale debe ser un número mayor que 0");
+                process::exit(1);
+            }
+        },
+    };
+    let mut window = Window::new(
+        "Raytracer",
+        ((width as f32 / render_scale) as usize).max(1),
+        ((height as f32 / render_scale) as usize).max(1),
+        WindowOptions {
+            resize: true,
+            ..WindowOptions::default()
+        },
+    )
+    .unwrap_or_else(|e| {
+        panic!("{}", e);
+    });
 
-    let cuboid7: Cuboid = Cuboid::new(
-        Vec3::new(0.0, 2.0, -1.0), // Centro del cubo
-        0.5,                       // Ancho del cubo
-        0.5,                       // Altura del cubo
-        0.5,                       // Profundidad del cubo
-        ladrillos_neg.clone(),     // Material de caucho
-    );
+    // Sin fuente el visor funciona igual, solo sin textos superpuestos
+    let overlay = Overlay::load(FONT_FILE)
+        .map_err(|e| eprintln!("No se pudo cargar la fuente: {}", e))
+        .ok();
+    let mut display = Framebuffer::new(width, height);
+    let mut selected: Option<usize> = None;
+    let mut selection_mask: Option<Vec<bool>> = None;
+    let mut hud = HudInfo {
+        fps: 0.0,
+        render_time: Duration::ZERO,
+        render_progress: 0.0,
+        stats: RayStats::default(),
+        mode: render_mode,
+        sun_angle: angle,
+        edit_material: None,
+    };
+    let mut show_help = false;
+    let mut edit_mode = false;
+    let mut edit_material = 0;
 
-    let cuboid8: Cuboid = Cuboid::new(
-        Vec3::new(0.0, 0.0, 2.0), // Centro del cubo
-        1.0,                      // Ancho del cubo
-        1.0,                      // Altura del cubo
-        1.0,                      // Profundidad del cubo
-        ladrillos_neg.clone(),    // Material de caucho
-    );
+    let mut needs_render = true;
+    let mut render_job: Option<RenderJob> = None;
+    let mut preview_framebuffer = Framebuffer::new(width / PREVIEW_SCALE, height / PREVIEW_SCALE);
+    let mut adaptive_preview = true;
+    let mut refine_pending = false;
+    let mut quality = Quality::FULL;
+    let mut denoiser: Option<Denoiser> = None;
+    let mut camera_mode = CameraMode::Orbit;
+    let mut last_frame = Instant::now();
+    let mut last_mouse_pos: Option<(f32, f32)> = None;
+    let mut input = match Input::load(INPUT_FILE) {
+        Ok(input) => input,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Input::default(),
+        Err(e) => {
+            eprintln!(
+                "No se pudo leer {}: {}; se usan los controles por defecto",
+                INPUT_FILE, e
+            );
+            Input::default()
+        }
+    };
 
-    let cuboid9: Cuboid = Cuboid::new(
-        Vec3::new(0.0, 1.0, 2.0), // Centro del cubo
-        1.0,                      // Ancho del cubo
-        1.0,                      // Altura del cubo
-        1.0,                      // Profundidad del cubo
-        ladrillos_neg.clone(),    // Material de caucho
-    );
+    while window.is_open() {
+        input.update(&window);
+        if input.active(Action::Quit) {
+            break;
+        }
 
-    let cuboid10: Cuboid = Cuboid::new(
-        Vec3::new(0.0, 2.0, 2.0), // Centro del cubo
-        0.5,                      // Ancho del cubo
-        0.5,                      // Altura del cubo
-        0.5,                      // Profundidad del cubo
-        ladrillos_neg.clone(),    // Material de caucho
-    );
+        // Al cambiar el tamaño de la ventana se renderiza a la nueva resolución, con la
+        // proporción de la ventana para que la imagen no se estire
+        let (window_width, window_height) = window.get_size();
+        let size = (
+            (window_width as f32 * render_scale) as usize,
+            (window_height as f32 * render_scale) as usize,
+        );
+        // Minimizada la ventana mide 0; se conserva la resolución anterior
+        if size.0 > 0 && size.1 > 0 && size != (width, height) {
+            (width, height) = size;
+            let mut resized = Framebuffer::new(width, height);
+            resized.upscale_from(&framebuffer); // Mostrar algo mientras llega el nuevo cuadro
+            framebuffer = resized;
+            display = Framebuffer::new(width, height);
+            preview_framebuffer = Framebuffer::new(
+                (width / PREVIEW_SCALE).max(1),
+                (height / PREVIEW_SCALE).max(1),
+            );
+            camera.aspect_ratio = width as f32 / height as f32;
+            selection_mask = None;
+            render_job = None;
+            needs_render = true;
+        }
 
-    let cuboid11: Cuboid = Cuboid::new(
-        Vec3::new(-3.0, 0.0, 2.0), // Centro del cubo
-        1.0,                       // Ancho del cubo
-        1.0,                       // Altura del cubo
-        1.0,                       // Profundidad del cubo
-        ladrillos_neg.clone(),     // Material de caucho
-    );
+        let elapsed_time = start_time.elapsed().as_secs_f32();
+        // Tiempo del cuadro anterior, para que el movimiento no dependa de los FPS
+        let delta_time = last_frame.elapsed().as_secs_f32();
+        last_frame = Instant::now();
+        if delta_time > 0.0 {
+            hud.fps += (1.0 / delta_time - hud.fps) * FPS_SMOOTHING;
+        }
 
-    let cuboid12: Cuboid = Cuboid::new(
-        Vec3::new(-3.0, 1.0, 2.0), // Centro del cubo
-        1.0,                       // Ancho del cubo
-        1.0,                       // Altura del cubo
-        1.0,                       // Profundidad del cubo
-        ladrillos_neg.clone(),     // Material de caucho
-    );
+        // Entrar o salir del modo edición
+        if input.active(Action::ToggleEdit) {
+            edit_mode = !edit_mode;
+        }
 
-    let cuboid13: Cuboid = Cuboid::new(
-        Vec3::new(-3.0, 2.0, 2.0), // Centro del cubo
-        0.5,                       // Ancho del cubo
-        0.5,                       // Altura del cubo
-        0.5,                       // Profundidad del cubo
-        ladrillos_neg.clone(),     // Material de caucho
-    );
+        // Mostrar u ocultar la ayuda
+        if input.active(Action::ToggleHelp) {
+            show_help = !show_help;
+        }
 
-    let cuboid14: Cuboid = Cuboid::new(
-        Vec3::new(-3.0, 0.0, -1.0), // Centro del cubo
-        1.0,                        // Ancho del cubo
-        1.0,                        // Altura del cubo
-        1.0,                        // Profundidad del cubo
-        ladrillos_neg.clone(),      // Material de caucho
-    );
+        // Alternar entre cámara orbital y vuelo libre
+        if input.active(Action::ToggleCameraMode) {
+            camera_mode = match camera_mode {
+                CameraMode::Orbit => CameraMode::FreeFly,
+                CameraMode::FreeFly => CameraMode::Orbit,
+            };
+        }
 
-    let cuboid15: Cuboid = Cuboid::new(
-        Vec3::new(-3.0, 1.0, -1.0), // Centro del cubo
-        1.0,                        // Ancho del cubo
-        1.0,                        // Altura del cubo
-        1.0,                        // Profundidad del cubo
-        ladrillos_neg.clone(),      // Material de caucho
-    );
+        if camera_mode == CameraMode::Orbit {
+            let orbit = (
+                input.axis(Action::OrbitRight, Action::OrbitLeft),
+                input.axis(Action::OrbitDown, Action::OrbitUp),
+            );
+            if orbit != (0.0, 0.0) {
+                camera.orbit(orbit.0, orbit.1);
+                needs_render = true;
+            }
 
-    let cuboid16: Cuboid = Cuboid::new(
-        Vec3::new(-3.0, 2.0, -1.0), // Centro del cubo
-        0.5,                        // Ancho del cubo
-        0.5,                        // Altura del cubo
-        0.5,                        // Profundidad del cubo
-        ladrillos_neg.clone(),      // Material de caucho
-    );
+            // Añadir control de zoom
+            let zoom = input.axis(Action::ZoomIn, Action::ZoomOut);
+            if zoom != 0.0 {
+                camera.zoom(zoom);
+                needs_render = true;
+            }
+        } else {
+            // Moverse en los tres ejes; las acciones rápida y lenta multiplican la velocidad
+            let movement = Vec3::new(
+                input.axis(Action::FlyLeft, Action::FlyRight),
+                input.axis(Action::FlyDown, Action::FlyUp),
+                input.axis(Action::FlyBack, Action::FlyForward),
+            );
+            if movement != Vec3::zeros() {
+                let mut speed = FLY_SPEED;
+                for modifier in [Action::FlyFast, Action::FlySlow] {
+                    if input.active(modifier) {
+                        speed *= input.amount(modifier);
+                    }
+                }
+                camera.fly(&(movement.normalize() * speed * delta_time));
+                needs_render = true;
+            }
 
-    // Suelo
-    let cuboid17: Cuboid = Cuboid::new(
-        Vec3::new(0.0, 0.0, 0.0), // Centro del cubo
-        1.0,                      // Ancho del cubo
-        1.0,                      // Altura del cubo
-        1.0,                      // Profundidad del cubo
-        texture_bricks.clone(),   // Material de caucho
-    );
+            // Mirar con las acciones de mirada
+            let mut look = (
+                input.axis(Action::LookLeft, Action::LookRight) * LOOK_SPEED * delta_time,
+                input.axis(Action::LookDown, Action::LookUp) * LOOK_SPEED * delta_time,
+            );
+
+            // Mirar con el ratón mientras se mantiene el botón de mirada
+            let mouse_pos = window.get_mouse_pos(MouseMode::Pass);
+            if !edit_mode && input.held(Action::MouseLook) {
+                if let (Some((x, y)), Some((last_x, last_y))) = (mouse_pos, last_mouse_pos) {
+                    look.0 += (x - last_x) * MOUSE_SENSITIVITY;
+                    look.1 -= (y - last_y) * MOUSE_SENSITIVITY;
+                }
+            }
+            last_mouse_pos = mouse_pos;
 
-    let cuboid18: Cuboid = Cuboid::new(
-        Vec3::new(0.0, 0.0, 1.0), // Centro del cubo
-        1.0,                      // Ancho del cubo
-        1.0,                      // Altura del cubo
-        1.0,                      // Profundidad del cubo
-        suelo.clone(),            // Material de caucho
-    );
+            if look != (0.0, 0.0) {
+                camera.look(look.0, look.1);
+                needs_render = true;
+            }
+        }
 
-    let cuboid19: Cuboid = Cuboid::new(
-        Vec3::new(-1.0, 0.0, 2.0),    // Centro del cubo
-        1.0,                          // Ancho del cubo
-        1.0,                          // Altura del cubo
-        1.0,                          // Profundidad del cubo
-        material_con_textura.clone(), // Material de caucho
-    );
+        // Ajustar el campo de visión
+        let fov_change = input.axis(Action::FovIn, Action::FovOut);
+        if fov_change != 0.0 {
+            camera.set_fov(camera.fov + fov_change);
+            needs_render = true;
+        }
 
-    let cuboid20: Cuboid = Cuboid::new(
-        Vec3::new(-2.0, 0.0, 2.0), // Centro del cubo
-        1.0,                       // Ancho del cubo
-        1.0,                       // Altura del cubo
-        1.0,                       // Profundidad del cubo
-        suelo.clone(),             // Material de caucho
-    );
+        // Abrir o cerrar la apertura del lente
+        let aperture_change = input.axis(Action::ApertureClose, Action::ApertureOpen);
+        if aperture_change != 0.0 {
+            camera.aperture = (camera.aperture + aperture_change).clamp(0.0, 0.5);
+            needs_render = true;
+        }
 
-    let cuboid21: Cuboid = Cuboid::new(
-        Vec3::new(-3.0, 0.0, 0.0), // Centro del cubo
-        1.0,                       // Ancho del cubo
-        1.0,                       // Altura del cubo
-        1.0,                       // Profundidad del cubo
-        texture_bricks.clone(),    // Material de caucho
-    );
+        // Cambiar la forma del bokeh
+        if input.active(Action::NextBokeh) {
+            camera.bokeh = camera.bokeh.next();
+            needs_render = true;
+        }
 
-    let cuboid22: Cuboid = Cuboid::new(
-        Vec3::new(-3.0, 0.0, 1.0),    // Centro del cubo
-        1.0,                          // Ancho del cubo
-        1.0,                          // Altura del cubo
-        1.0,                          // Profundidad del cubo
-        material_con_textura.clone(), // Material de caucho
-    );
+        // Grabar la vista actual como keyframe del recorrido
+        if input.active(Action::RecordKeyframe) {
+            let time = if camera_path.is_empty() {
+                0.0
+            } else {
+                camera_path.duration() + KEYFRAME_SPACING
+            };
+            camera_path.push(CameraKeyframe::from_camera(&camera, time));
+            match camera_path_file.map(|file| (file, camera_path.save(file))) {
+                Some((file, Ok(()))) => println!(
+                    "Keyframe {} en t = {:.1}s guardado en {}",
+                    camera_path.keyframes.len(),
+                    time,
+                    file
+                ),
+                Some((file, Err(e))) => eprintln!("No se pudo guardar {}: {}", file, e),
+                None => println!(
+                    "Keyframe {} en t = {:.1}s guardado solo hasta cerrar el visor",
+                    camera_path.keyframes.len(),
+                    time
+                ),
+            }
+        }
 
-    let cuboid23: Cuboid = Cuboid::new(
-        Vec3::new(-1.0, 0.0, -1.0), // Centro del cubo
-        1.0,                        // Ancho del cubo
-        1.0,                        // Altura del cubo
-        1.0,                        // Profundidad del cubo
-        suelo.clone(),              // Material de caucho
-    );
+        // Reproducir o detener el recorrido
+        if input.active(Action::TogglePlayback) {
+            playback_start = match playback_start {
+                Some(_) => None,
+                None if !camera_path.is_empty() => Some(Instant::now()),
+                None => None,
+            };
+        }
 
-    let cuboid24: Cuboid = Cuboid::new(
-        Vec3::new(-2.0, 0.0, -1.0), // Centro del cubo
-        1.0,                        // Ancho del cubo
-        1.0,                        // Altura del cubo
-        1.0,                        // Profundidad del cubo
-        texture_bricks.clone(),     // Material de caucho
-    );
+        if let Some(start) = playback_start {
+            let duration = camera_path.duration();
+            let time = if duration > 0.0 {
+                start.elapsed().as_secs_f32() % duration
+            } else {
+                0.0
+            };
+            camera_path.apply(&mut camera, time);
+            needs_render = true;
+        }
 
-    // Lava
-    let cuboid25: Cuboid = Cuboid::new(
-        Vec3::new(0.0, 0.0, -2.0), // Centro del cubo
-        1.0,                       // Ancho del cubo
-        1.0,                       // Altura del cubo
-        1.0,                       // Profundidad del cubo
-        lava.clone(),              // Material de caucho
-    );
+        // Marcadores: cada ranura recupera una vista y, con el modificador de guardado,
+        // la guarda. En el modo edición las ranuras 1-9 y 0 eligen el material de la paleta.
+        for slot in 0..BOOKMARK_SLOTS {
+            if input.active(Action::Slot(slot)) {
+                if edit_mode {
+                    let material = (slot + BOOKMARK_SLOTS - 1) % BOOKMARK_SLOTS;
+                    if material < scene.materials.len() {
+                        edit_material = material;
+                    }
+                } else if input.held(Action::SaveModifier) {
+                    let name = format!("Vista {}", slot);
+                    bookmarks.set(slot, Bookmark::from_camera(&name, &camera, angle));
+                    match &bookmarks_file {
+                        Some(file) => match bookmarks.save(file) {
+                            Ok(()) => println!("{} guardada en {}", name, file),
+                            Err(e) => eprintln!("No se pudo guardar {}: {}", file, e),
+                        },
+                        None => println!("{} guardada solo hasta cerrar el visor", name),
+                    }
+                } else if let Some(bookmark) = bookmarks.get(slot) {
+                    angle = bookmark.apply(&mut camera);
+                    needs_render = true;
+                }
+            }
+        }
 
-    let cuboid26: Cuboid = Cuboid::new(
-        Vec3::new(-1.0, 0.0, -2.0), // Centro del cubo
-        1.0,                        // Ancho del cubo
-        1.0,                        // Altura del cubo
-        1.0,                        // Profundidad del cubo
-        lava.clone(),               // Material de caucho
-    );
+        // Reproducir o pausar la línea de tiempo de la escena
+        if input.active(Action::ToggleTimeline) {
+            animation_start = match animation_start {
+                Some(_) => None,
+                None if !scene.timeline.is_empty() => Some(Instant::now()),
+                None => None,
+            };
+        }
 
-    let cuboid27: Cuboid = Cuboid::new(
-        Vec3::new(-2.0, 0.0, -2.0), // Centro del cubo
-        1.0,                        // Ancho del cubo
-        1.0,                        // Altura del cubo
-        1.0,                        // Profundidad del cubo
-        lava.clone(),               // Material de caucho
-    );
+        // Cambiar la proyección de la cámara
+        if input.active(Action::NextProjection) {
+            camera.projection = camera.projection.next();
+            needs_render = true;
+        }
 
-    let cuboid28: Cuboid = Cuboid::new(
-        Vec3::new(-3.0, 0.0, -2.0), // Centro del cubo
-        1.0,                        // Ancho del cubo
-        1.0,                        // Altura del cubo
-        1.0,                        // Profundidad del cubo
-        lava.clone(),               // Material de caucho
-    );
+        // Exportar la vista actual; los panoramas se renderizan a 2:1
+        if input.active(Action::Capture) {
+            let timestamp = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+                .unwrap_or(0);
+            let file_path = format!("captura_{}.png", timestamp);
+            let result = if camera.projection == Projection::Equirectangular {
+                let mut panorama = Framebuffer::new(PANORAMA_WIDTH, PANORAMA_HEIGHT);
+                render(
+                    &mut panorama,
+                    &scene,
+                    &camera,
+                    elapsed_time,
+                    quality,
+                    render_mode,
+                );
+                panorama.save(&file_path)
+            } else {
+                framebuffer.save(&file_path)
+            };
+            match result {
+                Ok(()) => println!("Imagen guardada en {}", file_path),
+                Err(e) => eprintln!("No se pudo guardar {}: {}", file_path, e),
+            }
+        }
 
-    let objects: Vec<Box<dyn RayIntersect>> = vec![
-        // Agua
-        Box::new(cuboid1),
-        Box::new(cuboid2),
-        Box::new(cuboid3),
-        Box::new(cuboid4),
-        // Columnas de ladrillos
-        Box::new(cuboid5),
-        Box::new(cuboid6),
-        Box::new(cuboid7),
-        Box::new(cuboid8),
-        Box::new(cuboid9),
-        Box::new(cuboid10),
-        Box::new(cuboid11),
-        Box::new(cuboid12),
-        Box::new(cuboid13),
-        Box::new(cuboid14),
-        Box::new(cuboid15),
-        Box::new(cuboid16),
-        // Suelo
-        Box::new(cuboid17),
-        Box::new(cuboid18),
-        Box::new(cuboid19),
-        Box::new(cuboid20),
-        Box::new(cuboid21),
-        Box::new(cuboid22),
-        Box::new(cuboid23),
-        Box::new(cuboid24),
-        // Lava
-        Box::new(cuboid25),
-        Box::new(cuboid26),
-        Box::new(cuboid27),
-        Box::new(cuboid28),
-    ];
+        // Exportar todas las vistas de depuración de la vista actual con F3
+        if input.active(Action::CaptureAovs) {
+            let timestamp = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+                .unwrap_or(0);
+            let prefix = format!("captura_{}", timestamp);
+            let context = RenderContext {
+                scene: &scene,
+                camera: &camera,
+                width,
+                height,
+                time: elapsed_time,
+                quality,
+                mode: RenderMode::Beauty,
+                motion: None,
+                placements: &[],
+            };
+            match save_aovs(&context, &RenderMode::ALL[1..], &prefix) {
+                Ok(()) => println!("Vistas guardadas en {}_*.png", prefix),
+                Err(e) => eprintln!("{}", e),
+            }
+        }
 
-    let mut angle = 1.0; // Ángulo para el movimiento de la luz
+        // Exportar los pases de la vista actual como un EXR de varias capas con F4
+        if input.active(Action::CapturePasses) {
+            let timestamp = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|duration| duration.as_secs())
+                .unwrap_or(0);
+            let file_path = format!("captura_{}.exr", timestamp);
+            let context = RenderContext {
+                scene: &scene,
+                camera: &camera,
+                width,
+                height,
+                time: elapsed_time,
+                quality,
+                mode: RenderMode::Beauty,
+                motion: None,
+                placements: &[],
+            };
+            match context.render_passes().save_exr(&file_path) {
+                Ok(()) => println!("Pases guardados en {}", file_path),
+                Err(e) => eprintln!("{}", e),
+            }
+        }
 
-    let mut use_normal_map = false;
+        // Seleccionar y enfocar el objeto bajo el cursor; en el cielo se quita la
+        // selección. En el modo edición coloca un bloque sobre la cara.
+        if input.active(Action::Select) {
+            if let Some((x, y)) = cursor_pixel(&window, width, height) {
+                let context = RenderContext {
+                    scene: &scene,
+                    camera: &camera,
+                    width,
+                    height,
+                    time: elapsed_time,
+                    quality,
+                    mode: render_mode,
+                    motion: None,
+                    placements: &[],
+                };
+                match context.pick(x, y) {
+                    Some((_, hit)) if edit_mode => {
+                        // Celda vecina del lado de la cara que se tocó
+                        let center = (hit.point + hit.normal * 0.5).map(f32::round);
+                        if scene.add_block(center, edit_material) {
+                            needs_render = true;
+                        }
+                    }
+                    Some((index, hit)) => {
+                        camera.focus_at(&hit.point);
+                        selected = Some(index);
+                        needs_render = true;
+                    }
+                    None => {
+                        selected = None;
+                        selection_mask = None;
+                    }
+                }
+            }
+        }
 
-    let width = 1300; // Reduce el tamaño a la mitad
-    let height = 900;
-    let mut framebuffer = Framebuffer::new(width, height);
+        // Modo edición: quitar el bloque bajo el cursor
+        if edit_mode && input.active(Action::RemoveBlock) {
+            if let Some(index) = pick_object(&window, &scene, &camera, width, height, elapsed_time)
+            {
+                scene.remove_object(index);
+                // Los índices cambiaron; la selección ya no es válida
+                selected = None;
+                selection_mask = None;
+                needs_render = true;
+            }
+        }
 
-    let mut window = Window::new(
-        "Raytracer",
-        (width as f32 / 1.3) as usize,
-        (height as f32 / 1.3) as usize,
-        WindowOptions::default(),
-    )
-    .unwrap_or_else(|e| {
-        panic!("{}", e);
-    });
+        // Modo edición: pintar el bloque bajo el cursor con el material elegido
+        if edit_mode && input.active(Action::PaintBlock) {
+            if let Some(index) = pick_object(&window, &scene, &camera, width, height, elapsed_time)
+            {
+                *scene.objects[index].material_mut() = scene.materials[edit_material].clone();
+                needs_render = true;
+            }
+        }
 
-    let mut needs_render = true;
-    let mut m_key_pressed = false;
+        // Guardar la escena editada
+        if input.active(Action::SaveScene) {
+            let file = scene_file.as_deref().unwrap_or(SCENE_FILE);
+            match scene.save_objects(file) {
+                Ok(()) => println!("Escena guardada en {}", file),
+                Err(e) => eprintln!("No se pudo guardar {}: {}", file, e),
+            }
+        }
 
-    while window.is_open() && !window.is_key_down(minifb::Key::Escape) {
-        let elapsed_time = start_time.elapsed().as_secs_f32();
+        // Pasar al siguiente modo de render
+        if input.active(Action::NextRenderMode) {
+            render_mode = render_mode.next();
+            println!("Modo de render: {}", render_mode.name());
+            needs_render = true; // Forzar el renderizado después de cambiar
+        }
 
-        if window.is_key_down(minifb::Key::Left) {
-            camera.orbit(0.05, 0.0);
+        // Activar o desactivar la vista previa a baja resolución
+        if input.active(Action::TogglePreview) {
+            adaptive_preview = !adaptive_preview;
             needs_render = true;
         }
-        if window.is_key_down(minifb::Key::Right) {
-            camera.orbit(-0.05, 0.0);
+
+        // Alternar el muestreo adaptativo por varianza
+        if input.active(Action::ToggleAdaptive) {
+            quality.adaptive = match quality.adaptive {
+                Some(_) => None,
+                None => Some(AdaptiveSampling::default()),
+            };
             needs_render = true;
         }
-        if window.is_key_down(minifb::Key::Up) {
-            camera.orbit(0.0, 0.05);
+
+        // Cambiar el filtro de ruido: ninguno, à-trous, bilateral
+        if input.active(Action::NextDenoiser) {
+            denoiser = match denoiser.map(|denoiser| denoiser.filter) {
+                None => Some(Denoiser::new(DenoiseFilter::ATrous)),
+                Some(DenoiseFilter::ATrous) => Some(Denoiser::new(DenoiseFilter::JointBilateral)),
+                Some(DenoiseFilter::JointBilateral) => None,
+            };
             needs_render = true;
         }
-        if window.is_key_down(minifb::Key::Down) {
-            camera.orbit(0.0, -0.05);
+
+        // Activar o desactivar la niebla
+        if input.active(Action::ToggleFog) {
+            scene.fog = match scene.fog.take() {
+                Some(_) => None,
+                None => Some(sunset_fog.clone()),
+            };
             needs_render = true;
         }
 
-        // Añadir control de zoom
-        if window.is_key_down(minifb::Key::W) {
-            camera.zoom(-0.1); // Acercar
+        // Activar o desactivar los rayos de luz volumétricos
+        if input.active(Action::ToggleVolumetrics) {
+            let fog = scene.fog.get_or_insert_with(|| sunset_fog.clone());
+            fog.volume = match fog.volume.take() {
+                Some(_) => None,
+                None => Some(light_shafts.clone()),
+            };
             needs_render = true;
         }
-        if window.is_key_down(minifb::Key::S) {
-            camera.zoom(0.1); // Alejar
+
+        // Controlar el ciclo de día y noche (con acciones propias en vuelo libre)
+        let sun_change = match camera_mode {
+            CameraMode::Orbit => input.axis(Action::SunBack, Action::SunForward),
+            CameraMode::FreeFly => input.axis(Action::FlySunBack, Action::FlySunForward),
+        };
+        if sun_change != 0.0 {
+            angle += sun_change;
             needs_render = true;
         }
 
-        // Alternar entre normal map y textura con la tecla "M"
-        if window.is_key_down(minifb::Key::M) {
-            if !m_key_pressed {
-                use_normal_map = !use_normal_map;
-                m_key_pressed = true;
-                needs_render = true; // Forzar el renderizado después de cambiar
-            }
-        } else {
-            m_key_pressed = false; // Restablecer el estado de la tecla "M"
-        }
+        // Actualizar la posición y el color de las múltiples luces
+        scene.set_sun_angle(angle);
 
-        // Controlar el ciclo de día y noche con las teclas A y D
-        if window.is_key_down(minifb::Key::A) {
-            angle -= 0.05; // Girar la luz en sentido antihorario
+        // La línea de tiempo se evalúa después del sol para poder animar las luces
+        if let Some(start) = animation_start {
+            scene.animate(start.elapsed().as_secs_f32());
             needs_render = true;
         }
-        if window.is_key_down(minifb::Key::D) {
-            angle += 0.05; // Girar la luz en sentido horario
-            needs_render = true;
+
+        // Mientras haya cambios se muestra una vista previa barata y estirada; el
+        // primer cuadro sin cambios empieza a refinarla a calidad completa
+        if needs_render && adaptive_preview {
+            let context = RenderContext {
+                scene: &scene,
+                camera: &camera,
+                width: preview_framebuffer.width,
+                height: preview_framebuffer.height,
+                time: elapsed_time,
+                quality: Quality::PREVIEW,
+                mode: render_mode,
+                motion: None,
+                placements: &[],
+            };
+            let preview_start = Instant::now();
+            hud.stats = context.render(&mut preview_framebuffer);
+            hud.render_time = preview_start.elapsed();
+            hud.render_progress = 1.0;
+            framebuffer.upscale_from(&preview_framebuffer);
+            // El resaltado anterior ya no coincide con la vista en movimiento
+            selection_mask = None;
+            render_job = None;
+            refine_pending = true;
+            needs_render = false;
+        } else if needs_render || refine_pending {
+            // Cualquier cambio abandona el cuadro en curso y empieza uno nuevo
+            render_job = Some(RenderJob::new(
+                width,
+                height,
+                VIEWER_TILE_SIZE,
+                VIEWER_TILE_ORDER,
+                elapsed_time, // Pasar el tiempo para animación
+            ));
+            selection_mask = selected.map(|index| {
+                let context = RenderContext {
+                    scene: &scene,
+                    camera: &camera,
+                    width,
+                    height,
+                    time: elapsed_time,
+                    quality,
+                    mode: render_mode,
+                    motion: None,
+                    placements: &[],
+                };
+                context.object_mask(index)
+            });
+            refine_pending = false;
+            needs_render = false;
         }
 
-        // Actualizar la posición y el color de las múltiples luces
-        for light in &mut lights {
-            light.position.x = 10.0 * angle.cos();
-            light.position.z = 10.0 * angle.sin();
-            light.position.y = 10.0 * angle.sin();
-
-            if light.position.y > 0.0 {
-                let intensity_factor = (light.position.y / 10.0).clamp(0.0, 1.0);
-                light.color = Color::new(
-                    (255.0 * intensity_factor) as i32,
-                    (223.0 * intensity_factor) as i32,
-                    (191.0 * intensity_factor) as i32,
-                );
-                light.intensity = 2.0 * intensity_factor;
+        // Avanzar el cuadro en curso unos milisegundos y mostrar lo que haya
+        if let Some(job) = render_job.as_mut() {
+            let context = RenderContext {
+                scene: &scene,
+                camera: &camera,
+                width,
+                height,
+                time: job.time,
+                quality,
+                mode: render_mode,
+                motion: None,
+                placements: &[],
+            };
+            let done = job.render_for(&mut framebuffer, Some(FRAME_BUDGET), |tile| {
+                context.render_tile(tile)
+            });
+            hud.stats = job.stats;
+            hud.render_time = job.elapsed();
+            hud.render_progress = job.progress();
+            if done {
+                if let Some(denoiser) = &denoiser {
+                    denoiser.apply(&mut framebuffer, &context.render_features());
+                }
+                window.set_title("Raytracer");
+                render_job = None;
             } else {
-                let intensity_factor = (-light.position.y / 10.0).clamp(0.0, 1.0);
-                light.color = Color::new(
-                    (64.0 * intensity_factor) as i32,
-                    (96.0 * intensity_factor) as i32,
-                    (255.0 * intensity_factor) as i32,
-                );
-                light.intensity = 0.5 * intensity_factor;
+                window.set_title(&format!("Raytracer - {:.0}%", job.progress() * 100.0));
             }
         }
 
-        // Solo renderiza si hubo cambios
-        if needs_render {
-            render(
-                &mut framebuffer,
-                &objects,
-                &camera,
-                &lights,
-                use_normal_map,
-                elapsed_time,
-            ); // Pasar el tiempo para animación
-            needs_render = false;
+        // Resaltado, inspector y HUD se dibujan sobre una copia para no ensuciar el cuadro
+        display.buffer.copy_from_slice(&framebuffer.buffer);
+        if let Some(mask) = &selection_mask {
+            highlight(&mut display, mask, HIGHLIGHT_COLOR);
         }
+        if let Some(overlay) = &overlay {
+            if let Some(index) = selected {
+                if let Some(object) = scene.objects.get(index) {
+                    let lines = inspector_lines(index, object.as_ref());
+                    overlay.draw_panel(&mut display, OVERLAY_MARGIN, OVERLAY_MARGIN, &lines);
+                }
+            }
 
-        /*
-        render(
-                &mut framebuffer,
-                &objects,
-                &camera,
-                &lights,
-                use_normal_map,
-                elapsed_time,
-            ); // Pasar el tiempo para animación
-        */
+            hud.mode = render_mode;
+            hud.sun_angle = angle;
+            hud.edit_material = edit_mode.then(|| {
+                scene
+                    .materials
+                    .get(edit_material)
+                    .map_or(String::new(), |material| material.name.clone())
+            });
+            let lines = hud_lines(&hud, &input);
+            let (_, panel_height) = overlay.panel_size(&lines);
+            let hud_y = height.saturating_sub(panel_height + OVERLAY_MARGIN);
+            overlay.draw_panel(&mut display, OVERLAY_MARGIN, hud_y, &lines);
+
+            if show_help {
+                let lines = help_lines(&input);
+                let (panel_width, _) = overlay.panel_size(&lines);
+                let help_x = width.saturating_sub(panel_width + OVERLAY_MARGIN);
+                overlay.draw_panel(&mut display, help_x, OVERLAY_MARGIN, &lines);
+            }
+        }
 
         window
-            .update_with_buffer(&framebuffer.to_u32_buffer(), width, height)
+            .update_with_buffer(&display.to_u32_buffer(), width, height)
             .unwrap();
     }
 }
+
+// Una línea por entrada de `HELP` con las teclas asignadas en los controles
+fn help_lines(input: &Input) -> Vec<String> {
+    let bindings: Vec<(String, &str)> = HELP
+        .iter()
+        .map(|(actions, description)| {
+            let names: Vec<String> = actions
+                .iter()
+                .map(|action| input.describe(*action))
+                .collect();
+            // Los marcadores son un rango de teclas: solo se muestran los extremos
+            let separator = if matches!(actions[0], Action::Slot(_)) {
+                ".."
+            } else {
+                " "
+            };
+            (names.join(separator), *description)
+        })
+        .collect();
+    let column = bindings
+        .iter()
+        .map(|(names, _)| names.chars().count())
+        .max()
+        .unwrap_or(0);
+    bindings
+        .iter()
+        .map(|(names, description)| format!("{:<column$}  {}", names, description))
+        .collect()
+}
+
+// Datos de rendimiento y estado que muestra el HUD del visor
+struct HudInfo {
+    fps: f32,
+    render_time: Duration, // Del cuadro en curso o del último terminado
+    render_progress: f32,
+    stats: RayStats,
+    mode: RenderMode,
+    sun_angle: f32,                // Radianes
+    edit_material: Option<String>, // Material elegido si se está editando
+}
+
+// Píxel del framebuffer bajo el cursor; la ventana puede tener otro tamaño
+fn cursor_pixel(window: &Window, width: usize, height: usize) -> Option<(usize, usize)> {
+    let (mouse_x, mouse_y) = window.get_mouse_pos(MouseMode::Discard)?;
+    let (window_width, window_height) = window.get_size();
+    let x = (mouse_x / window_width as f32 * width as f32) as usize;
+    let y = (mouse_y / window_height as f32 * height as f32) as usize;
+    Some((x, y))
+}
+
+// Índice del objeto bajo el cursor, con los rayos de un framebuffer de `width` x `height`
+fn pick_object(
+    window: &Window,
+    scene: &Scene,
+    camera: &Camera,
+    width: usize,
+    height: usize,
+    time: f32,
+) -> Option<usize> {
+    let (x, y) = cursor_pixel(window, width, height)?;
+    let context = RenderContext {
+        scene,
+        camera,
+        width,
+        height,
+        time,
+        quality: Quality::PREVIEW,
+        mode: RenderMode::Beauty,
+        motion: None,
+        placements: &[],
+    };
+    context.pick(x, y).map(|(index, _)| index)
+}
+
+fn hud_lines(hud: &HudInfo, input: &Input) -> Vec<String> {
+    let seconds = hud.render_time.as_secs_f32();
+    let rays_per_second = if seconds > 0.0 {
+        hud.stats.total() as f32 / seconds
+    } else {
+        0.0
+    };
+    let progress = if hud.render_progress < 1.0 {
+        format!(" ({:.0}%)", hud.render_progress * 100.0)
+    } else {
+        String::new()
+    };
+
+    let mut lines = vec![
+        format!(
+            "FPS: {:.1} ({:.1} ms)",
+            hud.fps,
+            1000.0 / hud.fps.max(0.001)
+        ),
+        format!("Render: {:.2} s{}", seconds, progress),
+        format!("Rayos/s: {:.2} M", rays_per_second / 1.0e6),
+        format!("Primarios:   {}", hud.stats.primary),
+        format!("Sombra:      {}", hud.stats.shadow),
+        format!("Secundarios: {}", hud.stats.secondary),
+        format!("Modo: {}", hud.mode.name()),
+        format!("Sol: {:.0}°", hud.sun_angle.to_degrees().rem_euclid(360.0)),
+    ];
+    if let Some(material) = &hud.edit_material {
+        lines.push(format!("Edición: {}", material));
+    }
+    lines.push(format!("{}: ayuda", input.describe(Action::ToggleHelp)));
+    lines
+}
+
+// Propiedades del objeto seleccionado para el panel del inspector
+fn inspector_lines(index: usize, object: &dyn RayIntersect) -> Vec<String> {
+    let center = object.center();
+    let size = object.size();
+    let material = object.material();
+    let albedo = material.albedo;
+    let name = if material.name.is_empty() {
+        "(sin nombre)"
+    } else {
+        &material.name
+    };
+    let texture = material
+        .texture
+        .as_ref()
+        .map_or("(ninguna)", |texture| texture.path.as_str());
+
+    vec![
+        format!("Objeto #{}", index),
+        format!(
+            "Centro:   ({:.2}, {:.2}, {:.2})",
+            center.x, center.y, center.z
+        ),
+        format!("Tamaño:   ({:.2}, {:.2}, {:.2})", size.x, size.y, size.z),
+        format!("Material: {}", name),
+        format!(
+            "Albedo:   [{:.2}, {:.2}, {:.2}, {:.2}]",
+            albedo[0], albedo[1], albedo[2], albedo[3]
+        ),
+        format!("Índice de refracción: {:.2}", material.refractive_index),
+        format!("Textura:  {}", texture),
+    ]
+}
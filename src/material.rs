@@ -1,16 +1,25 @@
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
 use std::sync::Arc;
 
 use crate::color::Color;
 use crate::texture::Texture;
+use nalgebra_glm::Vec3;
+
+// Texeles con opacidad menor o igual a este umbral se consideran vacíos (recorte alfa)
+pub const ALPHA_CUTOFF: f32 = 0.05;
 
 #[derive(Debug, Clone)]
 pub struct Material {
+    pub name: String, // Nombre para mostrar en el inspector
     pub diffuse: Color,
     pub specular: f32,
     pub albedo: [f32; 4],      // [difusa, especular, reflectiva, transparente]
     pub refractive_index: f32, // Índice de refracción
     pub texture: Option<Arc<Texture>>, // Referencia a la textura
     pub animation_speed: Option<(f32, f32)>, // Velocidad de animación de la textura en U y V
+    pub emission: f32,         // Intensidad con la que el material emite su propio color
+    pub tint: Color,           // Multiplica el color de la textura (blanco = sin cambio)
 }
 
 impl Material {
@@ -23,39 +32,106 @@ impl Material {
         animation_speed: Option<(f32, f32)>, // Velocidad de animación en U y V
     ) -> Self {
         Material {
+            name: String::new(),
             diffuse,
             specular,
             albedo,
             refractive_index,
             texture,
             animation_speed,
+            emission: 0.0,
+            tint: Color::new(255, 255, 255),
         }
     }
 
     pub fn black() -> Self {
         Material {
+            name: String::from("negro"),
             diffuse: Color::new(0, 0, 0), // Color negro
             specular: 0.0,
             albedo: [0.0, 0.0, 0.0, 0.0],
             refractive_index: 1.0,
             texture: None,
             animation_speed: None,
+            emission: 0.0,
+            tint: Color::new(255, 255, 255),
         }
     }
 
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+
+    // Identificador para las vistas de depuración: comparten id los materiales con la
+    // misma textura y propiedades (sin contar las que anima la línea de tiempo)
+    pub fn id(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        self.texture.as_ref().map(Arc::as_ptr).hash(&mut hasher);
+        for value in [
+            self.specular,
+            self.refractive_index,
+            self.albedo[0],
+            self.albedo[1],
+            self.albedo[2],
+        ] {
+            value.to_bits().hash(&mut hasher);
+        }
+        hasher.finish()
+    }
+
     // Función para obtener el color difuso de la textura
     pub fn get_diffuse_color(&self, u: f32, v: f32, time: f32) -> Color {
         if let Some(texture) = &self.texture {
-            // Desplazar las coordenadas de textura si hay animación
-            let (u_offset, v_offset) = self.animation_speed.unwrap_or((0.0, 0.0));
-            let animated_u = (u + time * u_offset) % 1.0; // Desplazamiento en U
-            let animated_v = (v + time * v_offset) % 1.0; // Desplazamiento en V
-
-            let x = (animated_u * (texture.width - 1) as f32) as usize;
-            let y = (animated_v * (texture.height - 1) as f32) as usize;
-            texture.get_pixel(x, y)
+            let (x, y) = self.texel_coords(texture, u, v, time);
+            texture
+                .get_pixel(x, y)
+                .filter(&(self.tint.to_vec3() / 255.0))
         } else {
             self.diffuse
         }
     }
+
+    // Opacidad de la textura en (u, v); los materiales sin textura son opacos
+    pub fn get_alpha(&self, u: f32, v: f32, time: f32) -> f32 {
+        if let Some(texture) = &self.texture {
+            let (x, y) = self.texel_coords(texture, u, v, time);
+            texture.get_alpha(x, y)
+        } else {
+            1.0
+        }
+    }
+
+    // Fracción de luz (r, g, b) que deja pasar la superficie, teñida por su color difuso
+    pub fn transmittance(&self, u: f32, v: f32, time: f32) -> Vec3 {
+        let transparency = self.albedo[3];
+        let opacity = self.get_alpha(u, v, time);
+        let material_transmittance = if transparency > 0.0 {
+            let color = self.get_diffuse_color(u, v, time);
+            let tint = Vec3::new(color.r as f32, color.g as f32, color.b as f32);
+            // Normalizar el tinte para conservar el tono sin oscurecer de más
+            let max_channel = tint.max();
+            if max_channel > 0.0 {
+                tint / max_channel * transparency
+            } else {
+                Vec3::zeros()
+            }
+        } else {
+            Vec3::zeros()
+        };
+
+        // La parte recortada por alfa deja pasar toda la luz
+        Vec3::repeat(1.0 - opacity) + material_transmittance * opacity
+    }
+
+    fn texel_coords(&self, texture: &Texture, u: f32, v: f32, time: f32) -> (usize, usize) {
+        // Desplazar las coordenadas de textura si hay animación
+        let (u_offset, v_offset) = self.animation_speed.unwrap_or((0.0, 0.0));
+        let animated_u = (u + time * u_offset) % 1.0; // Desplazamiento en U
+        let animated_v = (v + time * v_offset) % 1.0; // Desplazamiento en V
+
+        let x = (animated_u * (texture.width - 1) as f32) as usize;
+        let y = (animated_v * (texture.height - 1) as f32) as usize;
+        (x, y)
+    }
 }
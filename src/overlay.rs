@@ -0,0 +1,147 @@
+// Texto y marcas dibujados sobre la imagen del visor (inspector, HUD...)
+use crate::color::Color;
+use crate::framebuffer::Framebuffer;
+use rusttype::{point, Font, Scale};
+use std::fs;
+use std::io;
+
+const FONT_SIZE: f32 = 16.0;
+const PANEL_PADDING: usize = 8;
+const PANEL_OPACITY: f32 = 0.7;
+const PANEL_COLOR: Color = Color {
+    r: 20,
+    g: 20,
+    b: 30,
+};
+const TEXT_COLOR: Color = Color {
+    r: 235,
+    g: 235,
+    b: 235,
+};
+const HIGHLIGHT_TINT: f32 = 0.3;
+
+pub struct Overlay {
+    font: Font<'static>,
+    scale: Scale,
+}
+
+impl Overlay {
+    pub fn load(font_file: &str) -> io::Result<Self> {
+        let data = fs::read(font_file)?;
+        let font = Font::try_from_vec(data).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: fuente no válida", font_file),
+            )
+        })?;
+        Ok(Overlay {
+            font,
+            scale: Scale::uniform(FONT_SIZE),
+        })
+    }
+
+    pub fn line_height(&self) -> usize {
+        let metrics = self.font.v_metrics(self.scale);
+        (metrics.ascent - metrics.descent + metrics.line_gap).ceil() as usize
+    }
+
+    pub fn text_width(&self, text: &str) -> usize {
+        self.font
+            .layout(text, self.scale, point(0.0, 0.0))
+            .filter_map(|glyph| glyph.pixel_bounding_box())
+            .map(|bounds| bounds.max.x.max(0) as usize)
+            .max()
+            .unwrap_or(0)
+    }
+
+    // Escribe una línea con la esquina superior izquierda en (x, y), mezclando cada
+    // píxel según la cobertura del glifo
+    pub fn draw_text(
+        &self,
+        framebuffer: &mut Framebuffer,
+        x: usize,
+        y: usize,
+        text: &str,
+        color: Color,
+    ) {
+        let ascent = self.font.v_metrics(self.scale).ascent;
+        let origin = point(x as f32, y as f32 + ascent);
+        for glyph in self.font.layout(text, self.scale, origin) {
+            let Some(bounds) = glyph.pixel_bounding_box() else {
+                continue;
+            };
+            glyph.draw(|glyph_x, glyph_y, coverage| {
+                let pixel_x = bounds.min.x + glyph_x as i32;
+                let pixel_y = bounds.min.y + glyph_y as i32;
+                if pixel_x < 0 || pixel_y < 0 {
+                    return;
+                }
+                let (pixel_x, pixel_y) = (pixel_x as usize, pixel_y as usize);
+                if pixel_x < framebuffer.width && pixel_y < framebuffer.height {
+                    let pixel = &mut framebuffer.buffer[pixel_y * framebuffer.width + pixel_x];
+                    *pixel = pixel.blend(color, coverage);
+                }
+            });
+        }
+    }
+
+    // Ancho y alto del recuadro que ocupa `draw_panel` con estas líneas
+    pub fn panel_size(&self, lines: &[String]) -> (usize, usize) {
+        let width = lines
+            .iter()
+            .map(|line| self.text_width(line))
+            .max()
+            .unwrap_or(0);
+        (
+            width + 2 * PANEL_PADDING,
+            lines.len() * self.line_height() + 2 * PANEL_PADDING,
+        )
+    }
+
+    // Recuadro semitransparente con una línea de texto por renglón
+    pub fn draw_panel(&self, framebuffer: &mut Framebuffer, x: usize, y: usize, lines: &[String]) {
+        let line_height = self.line_height();
+        let (width, height) = self.panel_size(lines);
+
+        for pixel_y in y..(y + height).min(framebuffer.height) {
+            for pixel_x in x..(x + width).min(framebuffer.width) {
+                let pixel = &mut framebuffer.buffer[pixel_y * framebuffer.width + pixel_x];
+                *pixel = pixel.blend(PANEL_COLOR, PANEL_OPACITY);
+            }
+        }
+
+        for (row, line) in lines.iter().enumerate() {
+            let line_y = y + PANEL_PADDING + row * line_height;
+            self.draw_text(framebuffer, x + PANEL_PADDING, line_y, line, TEXT_COLOR);
+        }
+    }
+}
+
+// Tiñe los píxeles marcados en `mask` y pinta su contorno con `color`
+pub fn highlight(framebuffer: &mut Framebuffer, mask: &[bool], color: Color) {
+    let (width, height) = (framebuffer.width, framebuffer.height);
+    let selected = |x: usize, y: usize| mask[y * width + x];
+
+    for y in 0..height {
+        for x in 0..width {
+            if !selected(x, y) {
+                continue;
+            }
+            // Borde: algún vecino fuera de la selección o del cuadro
+            let border = x == 0
+                || y == 0
+                || x == width - 1
+                || y == height - 1
+                || !selected(x - 1, y)
+                || !selected(x + 1, y)
+                || !selected(x, y - 1)
+                || !selected(x, y + 1);
+            let pixel = &mut framebuffer.buffer[y * width + x];
+            *pixel = if border {
+                color
+            } else {
+                pixel.blend(color, HIGHLIGHT_TINT)
+            };
+        }
+    }
+}
@@ -0,0 +1,142 @@
+use crate::color::Color;
+use exr::prelude::{
+    AnyChannel, AnyChannels, Encoding, FlatSamples, Image, Layer, LayerAttributes, WritableImage,
+};
+use image::{ImageBuffer, Rgb};
+use nalgebra_glm::Vec3;
+
+// Distancia que se ve negra en el PNG de profundidad (el EXR guarda la distancia real)
+const PNG_DEPTH_RANGE: f32 = 20.0;
+
+// Extrae un pase de color de un píxel
+type PassFn = fn(&PixelPasses) -> Vec3;
+
+// Pases de un píxel para composición. Los colores son radiancia sin recortar: 1 es el
+// blanco de la imagen de 8 bits y los brillos y la emisión pueden superarlo
+#[derive(Debug, Clone, Copy)]
+pub struct PixelPasses {
+    pub beauty: Vec3,
+    pub diffuse: Vec3,
+    pub specular: Vec3,
+    pub reflection: Vec3,
+    pub refraction: Vec3,
+    pub shadow: Vec3, // Luz que llega desde las luces, (1, 1, 1) sin sombra
+    pub depth: f32,   // Distancia desde la cámara
+    pub normal: Vec3,
+    pub object_id: f32, // Índice del objeto más uno, 0 para el cielo
+}
+
+impl PixelPasses {
+    pub fn new() -> Self {
+        PixelPasses {
+            beauty: Vec3::zeros(),
+            diffuse: Vec3::zeros(),
+            specular: Vec3::zeros(),
+            reflection: Vec3::zeros(),
+            refraction: Vec3::zeros(),
+            shadow: Vec3::zeros(),
+            depth: 0.0,
+            normal: Vec3::zeros(),
+            object_id: 0.0,
+        }
+    }
+}
+
+// Todos los pases de un cuadro
+pub struct RenderPasses {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<PixelPasses>,
+}
+
+impl RenderPasses {
+    // Un EXR de una sola parte en el que cada pase es una capa según la convención de
+    // OpenEXR: el nombre de la capa es el prefijo de sus canales (`diffuse.R`, `depth.Z`...).
+    // La imagen final va en R, G y B, sin prefijo, para que cualquier visor la muestre
+    pub fn save_exr(&self, file_path: &str) -> Result<(), String> {
+        let color_pass = |prefix: &str, pass: PassFn| {
+            ["R", "G", "B"]
+                .iter()
+                .enumerate()
+                .map(|(channel, suffix)| {
+                    let name = if prefix.is_empty() {
+                        suffix.to_string()
+                    } else {
+                        format!("{}.{}", prefix, suffix)
+                    };
+                    self.channel(&name, |pixel| pass(pixel)[channel])
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let mut channels = Vec::new();
+        channels.extend(color_pass("", |pixel| pixel.beauty));
+        channels.extend(color_pass("diffuse", |pixel| pixel.diffuse));
+        channels.extend(color_pass("specular", |pixel| pixel.specular));
+        channels.extend(color_pass("reflection", |pixel| pixel.reflection));
+        channels.extend(color_pass("refraction", |pixel| pixel.refraction));
+        channels.extend(color_pass("shadow", |pixel| pixel.shadow));
+        channels.push(self.channel("depth.Z", |pixel| pixel.depth));
+        for (axis, name) in ["normal.X", "normal.Y", "normal.Z"].iter().enumerate() {
+            channels.push(self.channel(name, |pixel| pixel.normal[axis]));
+        }
+        channels.push(self.channel("object_id.id", |pixel| pixel.object_id));
+
+        let layer = Layer::new(
+            (self.width, self.height),
+            LayerAttributes::default(),
+            Encoding::FAST_LOSSLESS,
+            AnyChannels::sort(channels.into()),
+        );
+        let image = Image::from_layer(layer);
+        image
+            .write()
+            .to_file(file_path)
+            .map_err(|e| format!("No se pudo guardar {}: {}", file_path, e))
+    }
+
+    // Un PNG de 16 bits por pase como `<prefijo>_<pase>.png`
+    pub fn save_pngs(&self, prefix: &str) -> Result<(), String> {
+        let passes: [(&str, PassFn); 9] = [
+            ("beauty", |pixel| pixel.beauty),
+            ("diffuse", |pixel| pixel.diffuse),
+            ("specular", |pixel| pixel.specular),
+            ("reflection", |pixel| pixel.reflection),
+            ("refraction", |pixel| pixel.refraction),
+            ("shadow", |pixel| pixel.shadow),
+            ("depth", |pixel| {
+                Vec3::repeat(1.0 - (pixel.depth / PNG_DEPTH_RANGE).min(1.0))
+            }),
+            ("normal", |pixel| (pixel.normal + Vec3::repeat(1.0)) * 0.5),
+            ("object_id", |pixel| {
+                if pixel.object_id > 0.0 {
+                    Color::from_id(pixel.object_id as u64 - 1).to_vec3() / 255.0
+                } else {
+                    Vec3::zeros()
+                }
+            }),
+        ];
+
+        for (name, pass) in passes {
+            let image = ImageBuffer::<Rgb<u16>, Vec<u16>>::from_fn(
+                self.width as u32,
+                self.height as u32,
+                |x, y| {
+                    let value = pass(&self.pixels[y as usize * self.width + x as usize]);
+                    let channel = |v: f32| (v.clamp(0.0, 1.0) * 65535.0).round() as u16;
+                    Rgb([channel(value.x), channel(value.y), channel(value.z)])
+                },
+            );
+            let file_path = format!("{}_{}.png", prefix, name);
+            image
+                .save(&file_path)
+                .map_err(|e| format!("No se pudo guardar {}: {}", file_path, e))?;
+        }
+        Ok(())
+    }
+
+    fn channel(&self, name: &str, value: impl Fn(&PixelPasses) -> f32) -> AnyChannel<FlatSamples> {
+        let samples = self.pixels.iter().map(value).collect();
+        AnyChannel::new(name, FlatSamples::F32(samples))
+    }
+}
@@ -46,5 +46,28 @@ impl Intersect {
 }
 
 pub trait RayIntersect: Send + Sync {
-    fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Intersect;
+    fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Intersect {
+        self.ray_intersect_at(ray_origin, ray_direction, &self.center(), &self.size())
+    }
+
+    // Intersección con el objeto como si estuviera en `center` con tamaño `size`, sin
+    // modificarlo; el desenfoque de movimiento la usa con la posición de cada instante
+    fn ray_intersect_at(
+        &self,
+        ray_origin: &Vec3,
+        ray_direction: &Vec3,
+        center: &Vec3,
+        size: &Vec3,
+    ) -> Intersect;
+
+    // Acceso para animar el objeto
+    fn set_center(&mut self, center: Vec3);
+    fn set_size(&mut self, size: Vec3); // (ancho, alto, profundidad); las esferas usan x como radio
+    fn material_mut(&mut self) -> &mut Material;
+
+    // Lectura de las mismas propiedades para el inspector
+    fn center(&self) -> Vec3;
+    fn size(&self) -> Vec3;
+    fn material(&self) -> &Material;
+    fn kind(&self) -> &'static str; // Nombre del tipo de objeto en el archivo de escena
 }
@@ -0,0 +1,378 @@
+// Pruebas con imágenes de referencia: renderizan escenas pequeñas y deterministas por el
+// mismo camino que el visor y las comparan con los PNG de `tests/reference`. Si alguna
+// no coincide, deja la imagen obtenida y la de diferencias en `target/reference-diffs`.
+// Con `UPDATE_REFERENCES=1 cargo test` se reescriben las referencias en vez de compararlas.
+use crate::camera::Camera;
+use crate::color::Color;
+use crate::cuboid::Cuboid;
+use crate::framebuffer::Framebuffer;
+use crate::light::Light;
+use crate::material::Material;
+use crate::ray_intersect::RayIntersect;
+use crate::render_mode::RenderMode;
+use crate::renderer::{Quality, RenderContext};
+use crate::scene::Scene;
+use crate::sphere::Sphere;
+use crate::texture::Texture;
+use image::{Rgb, RgbImage};
+use nalgebra_glm::Vec3;
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+
+const WIDTH: usize = 80;
+const HEIGHT: usize = 60;
+const FOV: f32 = 45.0; // Más cerrado que el del visor para que los objetos llenen la imagen
+
+const REFERENCE_DIR: &str = "tests/reference";
+const DIFF_DIR: &str = "target/reference-diffs";
+
+// Tolerancias: el error cuadrático medio por canal (niveles de 0 a 255) y la fracción de
+// píxeles cuya diferencia perceptual supera PIXEL_THRESHOLD
+const MAX_RMSE: f64 = 2.0;
+const PIXEL_THRESHOLD: f64 = 16.0;
+const MAX_DIFFERENT_PIXELS: f64 = 0.005;
+
+// Cuánto se amplifican las diferencias en la imagen de diferencias
+const DIFF_GAIN: f64 = 4.0;
+
+// Renderiza con una cámara estenopeica, un rayo por píxel y sin muestreo adaptativo,
+// así que el resultado no depende del generador aleatorio
+fn render_scene(scene: &Scene, eye: Vec3, center: Vec3, mode: RenderMode) -> RgbImage {
+    let mut camera = Camera::new(eye, center, Vec3::new(0.0, 1.0, 0.0));
+    camera.aspect_ratio = WIDTH as f32 / HEIGHT as f32;
+    camera.set_fov(FOV);
+    let context = RenderContext {
+        scene,
+        camera: &camera,
+        width: WIDTH,
+        height: HEIGHT,
+        time: 0.0,
+        quality: Quality::FULL,
+        mode,
+        motion: None,
+        placements: &[],
+    };
+    let mut framebuffer = Framebuffer::new(WIDTH, HEIGHT);
+    context.render(&mut framebuffer);
+
+    RgbImage::from_fn(WIDTH as u32, HEIGHT as u32, |x, y| {
+        let color = framebuffer.buffer[y as usize * WIDTH + x as usize];
+        Rgb([color.r, color.g, color.b])
+    })
+}
+
+// Distancia "redmean": RGB euclidiano con pesos que dependen del rojo promedio, una
+// aproximación barata a la diferencia percibida. Va de 0 a 255
+fn perceptual_difference(a: &Rgb<u8>, b: &Rgb<u8>) -> f64 {
+    let red_mean = (a[0] as f64 + b[0] as f64) / 2.0;
+    let dr = a[0] as f64 - b[0] as f64;
+    let dg = a[1] as f64 - b[1] as f64;
+    let db = a[2] as f64 - b[2] as f64;
+    let weighted = (2.0 + red_mean / 256.0) * dr * dr
+        + 4.0 * dg * dg
+        + (2.0 + (255.0 - red_mean) / 256.0) * db * db;
+    weighted.sqrt() / 3.0
+}
+
+struct Comparison {
+    rmse: f64,
+    different_pixels: f64, // Fracción de píxeles sobre PIXEL_THRESHOLD
+    diff: RgbImage,        // Rojo donde supera el umbral, gris según la diferencia
+}
+
+impl Comparison {
+    fn passes(&self) -> bool {
+        self.rmse <= MAX_RMSE && self.different_pixels <= MAX_DIFFERENT_PIXELS
+    }
+}
+
+fn compare(actual: &RgbImage, expected: &RgbImage) -> Comparison {
+    let mut squared_error = 0.0;
+    let mut different = 0;
+    let mut diff = RgbImage::new(actual.width(), actual.height());
+
+    for (x, y, pixel) in actual.enumerate_pixels() {
+        let reference = expected.get_pixel(x, y);
+        for channel in 0..3 {
+            let error = pixel[channel] as f64 - reference[channel] as f64;
+            squared_error += error * error;
+        }
+
+        let difference = perceptual_difference(pixel, reference);
+        let level = (difference * DIFF_GAIN).min(255.0) as u8;
+        let diff_pixel = if difference > PIXEL_THRESHOLD {
+            different += 1;
+            Rgb([255, 0, 0])
+        } else {
+            Rgb([level, level, level])
+        };
+        diff.put_pixel(x, y, diff_pixel);
+    }
+
+    let pixels = (actual.width() * actual.height()) as f64;
+    Comparison {
+        rmse: (squared_error / (pixels * 3.0)).sqrt(),
+        different_pixels: different as f64 / pixels,
+        diff,
+    }
+}
+
+fn assert_matches_reference(name: &str, actual: &RgbImage) {
+    let reference_path = Path::new(REFERENCE_DIR).join(format!("{}.png", name));
+
+    if env::var_os("UPDATE_REFERENCES").is_some() {
+        fs::create_dir_all(REFERENCE_DIR).unwrap();
+        actual.save(&reference_path).unwrap();
+        return;
+    }
+
+    let expected = match image::open(&reference_path) {
+        Ok(image) => image.to_rgb8(),
+        Err(error) => panic!(
+            "no se pudo leer {}: {} (generala con UPDATE_REFERENCES=1 cargo test)",
+            reference_path.display(),
+            error
+        ),
+    };
+    assert_eq!(
+        expected.dimensions(),
+        actual.dimensions(),
+        "{}: la referencia tiene otro tamaño",
+        name
+    );
+
+    let comparison = compare(actual, &expected);
+    if !comparison.passes() {
+        fs::create_dir_all(DIFF_DIR).unwrap();
+        let actual_path = Path::new(DIFF_DIR).join(format!("{}_actual.png", name));
+        let diff_path = Path::new(DIFF_DIR).join(format!("{}_diff.png", name));
+        actual.save(&actual_path).unwrap();
+        comparison.diff.save(&diff_path).unwrap();
+        panic!(
+            "{}: RMSE {:.3} (máximo {}), {:.2}% de píxeles distintos (máximo {:.2}%); ver {} y {}",
+            name,
+            comparison.rmse,
+            MAX_RMSE,
+            comparison.different_pixels * 100.0,
+            MAX_DIFFERENT_PIXELS * 100.0,
+            actual_path.display(),
+            diff_path.display()
+        );
+    }
+}
+
+fn matte(color: Color) -> Material {
+    Material::new(color, 10.0, [0.9, 0.1, 0.0, 0.0], 1.0, None, None)
+}
+
+fn floor(material: Material) -> Box<dyn RayIntersect> {
+    Box::new(Cuboid::new(
+        Vec3::new(0.0, -1.5, 0.0),
+        8.0,
+        1.0,
+        8.0,
+        material,
+    ))
+}
+
+fn white_light(position: Vec3) -> Light {
+    Light::new(position, Color::new(255, 255, 255), 1.5)
+}
+
+#[test]
+fn cuboid_uv_mapping() {
+    // Tres caras visibles; rojo y verde son u y v
+    let scene = Scene::new(
+        vec![Box::new(Cuboid::new(
+            Vec3::zeros(),
+            2.0,
+            1.5,
+            1.0,
+            matte(Color::new(255, 255, 255)),
+        ))],
+        vec![white_light(Vec3::new(3.0, 5.0, 4.0))],
+    );
+    let image = render_scene(
+        &scene,
+        Vec3::new(2.5, 2.0, 3.0),
+        Vec3::zeros(),
+        RenderMode::Uvs,
+    );
+    assert_matches_reference("cuboid_uv_mapping", &image);
+}
+
+#[test]
+fn cuboid_texture() {
+    // La misma vista con una textura, para ver la orientación de cada cara
+    let texture = Arc::new(Texture::new("assets/ladrillos.png").unwrap());
+    let material = Material::new(
+        Color::new(255, 255, 255),
+        50.0,
+        [0.9, 0.1, 0.0, 0.0],
+        1.0,
+        Some(texture),
+        None,
+    );
+    let scene = Scene::new(
+        vec![Box::new(Cuboid::new(
+            Vec3::zeros(),
+            2.0,
+            1.5,
+            1.0,
+            material,
+        ))],
+        vec![white_light(Vec3::new(3.0, 5.0, 4.0))],
+    );
+    let image = render_scene(
+        &scene,
+        Vec3::new(2.5, 2.0, 3.0),
+        Vec3::zeros(),
+        RenderMode::Beauty,
+    );
+    assert_matches_reference("cuboid_texture", &image);
+}
+
+#[test]
+fn sphere_shading() {
+    let material = Material::new(
+        Color::new(220, 60, 60),
+        50.0,
+        [0.8, 0.4, 0.0, 0.0],
+        1.0,
+        None,
+        None,
+    );
+    let scene = Scene::new(
+        vec![Box::new(Sphere::new(Vec3::zeros(), 1.0, material))],
+        vec![white_light(Vec3::new(3.0, 4.0, 4.0))],
+    );
+    let image = render_scene(
+        &scene,
+        Vec3::new(0.0, 0.0, 3.0),
+        Vec3::zeros(),
+        RenderMode::Beauty,
+    );
+    assert_matches_reference("sphere_shading", &image);
+}
+
+#[test]
+fn reflection() {
+    // Piso espejo con un cubo y una esfera encima
+    let mirror = Material::new(
+        Color::new(200, 200, 200),
+        200.0,
+        [0.2, 0.2, 0.8, 0.0],
+        1.0,
+        None,
+        None,
+    );
+    let scene = Scene::new(
+        vec![
+            floor(mirror),
+            Box::new(Cuboid::new(
+                Vec3::new(-0.8, -0.5, 0.0),
+                1.0,
+                1.0,
+                1.0,
+                matte(Color::new(200, 40, 40)),
+            )),
+            Box::new(Sphere::new(
+                Vec3::new(0.9, -0.4, 0.3),
+                0.6,
+                matte(Color::new(40, 60, 200)),
+            )),
+        ],
+        vec![white_light(Vec3::new(2.0, 5.0, 3.0))],
+    );
+    let image = render_scene(
+        &scene,
+        Vec3::new(0.0, 1.0, 4.0),
+        Vec3::new(0.0, -0.5, 0.0),
+        RenderMode::Beauty,
+    );
+    assert_matches_reference("reflection", &image);
+}
+
+#[test]
+fn refraction() {
+    // Una esfera y un cubo de vidrio delante de franjas de colores
+    let glass = Material::new(
+        Color::new(255, 255, 255),
+        125.0,
+        [0.0, 0.3, 0.1, 0.8],
+        1.5,
+        None,
+        None,
+    );
+    let stripes = [
+        Color::new(200, 40, 40),
+        Color::new(40, 200, 40),
+        Color::new(40, 40, 200),
+        Color::new(220, 220, 40),
+    ];
+    let mut objects: Vec<Box<dyn RayIntersect>> = stripes
+        .iter()
+        .enumerate()
+        .map(|(index, color)| {
+            Box::new(Cuboid::new(
+                Vec3::new(index as f32 - 1.5, 0.0, -2.0),
+                1.0,
+                4.0,
+                0.2,
+                matte(*color),
+            )) as Box<dyn RayIntersect>
+        })
+        .collect();
+    objects.push(Box::new(Sphere::new(
+        Vec3::new(-0.7, 0.0, 0.0),
+        0.6,
+        glass.clone(),
+    )));
+    objects.push(Box::new(Cuboid::new(
+        Vec3::new(0.8, 0.0, 0.0),
+        0.9,
+        0.9,
+        0.9,
+        glass,
+    )));
+    let scene = Scene::new(objects, vec![white_light(Vec3::new(0.0, 3.0, 4.0))]);
+    let image = render_scene(
+        &scene,
+        Vec3::new(0.0, 0.0, 3.0),
+        Vec3::zeros(),
+        RenderMode::Beauty,
+    );
+    assert_matches_reference("refraction", &image);
+}
+
+#[test]
+fn shadows() {
+    // Luz baja y lateral para que el cubo y la esfera proyecten sombras largas
+    let scene = Scene::new(
+        vec![
+            floor(matte(Color::new(180, 180, 180))),
+            Box::new(Cuboid::new(
+                Vec3::new(-0.8, -0.5, 0.0),
+                1.0,
+                1.0,
+                1.0,
+                matte(Color::new(200, 120, 40)),
+            )),
+            Box::new(Sphere::new(
+                Vec3::new(1.0, -0.5, -0.5),
+                0.5,
+                matte(Color::new(60, 160, 200)),
+            )),
+        ],
+        vec![white_light(Vec3::new(-4.0, 2.0, 1.0))],
+    );
+    let image = render_scene(
+        &scene,
+        Vec3::new(0.0, 3.0, 4.0),
+        Vec3::new(0.0, -0.8, 0.0),
+        RenderMode::Beauty,
+    );
+    assert_matches_reference("shadows", &image);
+}
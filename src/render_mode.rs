@@ -0,0 +1,69 @@
+// Qué se pinta en cada píxel: la imagen final o una vista de depuración
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RenderMode {
+    Beauty,
+    Depth,          // Distancia a la cámara, blanco cerca
+    WorldPosition,  // Posición del impacto como (r, g, b)
+    Normals,        // Normal del impacto llevada a [0, 1]
+    LitNormals,     // Normales como color de la superficie, iluminadas por las luces
+    Uvs,            // Coordenadas de textura en rojo y verde
+    MaterialId,     // Un color por material
+    ObjectId,       // Un color por objeto
+    ShadowOnly,     // Luz que llega desde las luces, blanco sin sombra
+    DirectOnly,     // Emisión más iluminación directa, sin rebotes
+    ReflectionOnly, // Aporte del rayo reflejado
+    BounceCount,    // Rebote más profundo alcanzado, como mapa de calor
+    RayCost,        // Rayos lanzados por píxel, como mapa de calor
+    SampleCount,    // Muestras por píxel, como mapa de calor
+}
+
+impl RenderMode {
+    pub const ALL: [RenderMode; 14] = [
+        RenderMode::Beauty,
+        RenderMode::Depth,
+        RenderMode::WorldPosition,
+        RenderMode::Normals,
+        RenderMode::LitNormals,
+        RenderMode::Uvs,
+        RenderMode::MaterialId,
+        RenderMode::ObjectId,
+        RenderMode::ShadowOnly,
+        RenderMode::DirectOnly,
+        RenderMode::ReflectionOnly,
+        RenderMode::BounceCount,
+        RenderMode::RayCost,
+        RenderMode::SampleCount,
+    ];
+
+    pub fn next(&self) -> RenderMode {
+        let index = RenderMode::ALL
+            .iter()
+            .position(|mode| mode == self)
+            .unwrap_or(0);
+        RenderMode::ALL[(index + 1) % RenderMode::ALL.len()]
+    }
+
+    // Nombre usado en la línea de comandos y como sufijo de los archivos exportados
+    pub fn name(&self) -> &'static str {
+        match self {
+            RenderMode::Beauty => "beauty",
+            RenderMode::Depth => "depth",
+            RenderMode::WorldPosition => "position",
+            RenderMode::Normals => "normal",
+            RenderMode::LitNormals => "normal_lit",
+            RenderMode::Uvs => "uv",
+            RenderMode::MaterialId => "material_id",
+            RenderMode::ObjectId => "object_id",
+            RenderMode::ShadowOnly => "shadow",
+            RenderMode::DirectOnly => "direct",
+            RenderMode::ReflectionOnly => "reflection",
+            RenderMode::BounceCount => "bounces",
+            RenderMode::RayCost => "ray_cost",
+            RenderMode::SampleCount => "samples",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<RenderMode> {
+        RenderMode::ALL.into_iter().find(|mode| mode.name() == name)
+    }
+}
@@ -0,0 +1,1004 @@
+use crate::camera::Camera;
+use crate::camera_path::CameraPath;
+use crate::color::Color;
+use crate::denoise::FeatureBuffers;
+use crate::fog::{Volume, SKY_DISTANCE};
+use crate::framebuffer::Framebuffer;
+use crate::light::Light;
+use crate::material::ALPHA_CUTOFF;
+use crate::passes::{PixelPasses, RenderPasses};
+use crate::ray_intersect::{Intersect, RayIntersect};
+use crate::render_mode::RenderMode;
+use crate::sampling::{AdaptiveSampling, PixelEstimate};
+use crate::scene::{Placement, Scene};
+use crate::tiles::{RenderJob, Tile, TileOrder};
+use nalgebra_glm::Vec3;
+use rand::Rng;
+use rayon::prelude::*;
+use std::cell::RefCell;
+use std::ops::AddAssign;
+
+// Lado de los mosaicos en que se divide cada cuadro
+pub const TILE_SIZE: usize = 32;
+
+const SKY_COLOR: Color = Color {
+    r: 0,
+    g: 90,
+    b: 150,
+};
+
+// Muestras por píxel cuando la apertura del lente está abierta
+const DOF_SAMPLES: u32 = 16;
+
+// Escalas de las vistas de depuración
+const DEPTH_RANGE: f32 = 20.0; // Distancia que se ve negra
+const POSITION_RANGE: f32 = 10.0; // Lado del cubo centrado en el origen que cubre los colores
+const RAY_COST_SCALE: f32 = 16.0; // Rayos por píxel que se ven rojos
+
+// Separación de los rayos secundarios respecto de la superficie de la que salen
+const SURFACE_BIAS: f32 = 0.01;
+
+// Máximo de superficies recortadas por alfa que atraviesa un rayo antes de rendirse
+const MAX_ALPHA_SKIPS: u32 = 8;
+
+thread_local! {
+    // Posiciones de los objetos en el instante de cada muestra del desenfoque de
+    // movimiento, una lista por hilo que se reutiliza entre muestras
+    static SAMPLE_PLACEMENTS: RefCell<Vec<Option<Placement>>> = const { RefCell::new(Vec::new()) };
+}
+
+fn reflect(incident: &Vec3, normal: &Vec3) -> Vec3 {
+    incident - 2.0 * incident.dot(normal) * normal
+}
+
+// Origen de un rayo secundario que sale de `point` en `direction`, separado de la
+// superficie hacia el lado al que va el rayo. La normal apunta hacia afuera del objeto
+// aunque el rayo venga de adentro, así que el lado sale del rayo y no de la normal
+fn offset_origin(point: &Vec3, normal: &Vec3, direction: &Vec3) -> Vec3 {
+    if direction.dot(normal) < 0.0 {
+        point - normal * SURFACE_BIAS
+    } else {
+        point + normal * SURFACE_BIAS
+    }
+}
+
+// Intersección con el objeto `index`, en su lugar de `placements` si está animado
+fn intersect_object(
+    index: usize,
+    object: &dyn RayIntersect,
+    placements: &[Option<Placement>],
+    ray_origin: &Vec3,
+    ray_direction: &Vec3,
+) -> Intersect {
+    match placements.get(index).copied().flatten() {
+        Some(placement) => object.ray_intersect_at(
+            ray_origin,
+            ray_direction,
+            &placement.center,
+            &placement.size,
+        ),
+        None => object.ray_intersect(ray_origin, ray_direction),
+    }
+}
+
+// Índice del objeto más cercano y su intersección, ignorando los texeles
+// completamente transparentes
+pub fn closest_opaque_intersect(
+    ray_origin: &Vec3,
+    ray_direction: &Vec3,
+    objects: &[Box<dyn RayIntersect>],
+    placements: &[Option<Placement>],
+    time: f32,
+    stats: &mut RayStats,
+) -> Option<(usize, Intersect)> {
+    let bias = 0.01;
+    let mut origin = *ray_origin;
+    let mut traveled = 0.0;
+
+    for _ in 0..MAX_ALPHA_SKIPS {
+        let mut closest: Option<(usize, Intersect)> = None;
+        let mut zbuffer = f32::INFINITY;
+
+        for (index, object) in objects.iter().enumerate() {
+            let tmp = intersect_object(index, object.as_ref(), placements, &origin, ray_direction);
+            stats.count_test(tmp.is_intersecting);
+            if tmp.is_intersecting && tmp.distance < zbuffer {
+                zbuffer = tmp.distance;
+                closest = Some((index, tmp));
+            }
+        }
+
+        let (index, mut closest_intersect) = closest?;
+
+        let alpha =
+            closest_intersect
+                .material
+                .get_alpha(closest_intersect.u, closest_intersect.v, time);
+        if alpha > ALPHA_CUTOFF {
+            closest_intersect.distance += traveled;
+            return Some((index, closest_intersect));
+        }
+
+        // Texel recortado: continuar el rayo justo detrás de la superficie
+        let step = closest_intersect.distance + bias;
+        origin += ray_direction * step;
+        traveled += step;
+    }
+
+    None
+}
+
+// Compromiso entre velocidad y fidelidad de un render
+#[derive(Debug, Clone, Copy)]
+pub struct Quality {
+    pub max_depth: u32,    // Rebotes de reflexión, refracción y transparencia
+    pub shadows: bool,     // Rayos de sombra hacia cada luz
+    pub volumetrics: bool, // Rayos de luz en el medio de la niebla
+    pub lens_samples: u32, // Muestras por píxel con la apertura abierta
+    pub adaptive: Option<AdaptiveSampling>, // Reemplaza las muestras fijas si existe
+}
+
+impl Quality {
+    pub const FULL: Quality = Quality {
+        max_depth: 3,
+        shadows: true,
+        volumetrics: true,
+        lens_samples: DOF_SAMPLES,
+        adaptive: None,
+    };
+
+    // Para moverse por la escena: un rebote, sin sombras y con lente estenopeico
+    pub const PREVIEW: Quality = Quality {
+        max_depth: 1,
+        shadows: false,
+        volumetrics: false,
+        lens_samples: 1,
+        adaptive: None,
+    };
+}
+
+// Rayos lanzados al sombrear, por tipo
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RayStats {
+    pub primary: u64,
+    pub shadow: u64, // Incluye los que marchan por el volumen de niebla
+    pub secondary: u64,
+    pub max_depth: u32,          // Rebote más profundo alcanzado
+    pub intersection_tests: u64, // Pruebas rayo-objeto, de todos los tipos de rayo
+    pub intersection_hits: u64,
+}
+
+impl RayStats {
+    pub fn total(&self) -> u64 {
+        self.primary + self.shadow + self.secondary
+    }
+
+    fn count_test(&mut self, hit: bool) {
+        self.intersection_tests += 1;
+        self.intersection_hits += hit as u64;
+    }
+}
+
+impl AddAssign for RayStats {
+    fn add_assign(&mut self, other: RayStats) {
+        self.primary += other.primary;
+        self.shadow += other.shadow;
+        self.secondary += other.secondary;
+        self.max_depth = self.max_depth.max(other.max_depth);
+        self.intersection_tests += other.intersection_tests;
+        self.intersection_hits += other.intersection_hits;
+    }
+}
+
+// Aportes por separado de un punto sombreado, como radiancia en la escala de 0 a 255 de
+// los colores pero sin recortar: solo se recorta al pasar a la imagen de 8 bits
+struct Shading {
+    emission: Vec3,
+    diffuse: Vec3,    // Luz difusa de todas las luces
+    specular: Vec3,   // Brillos especulares de todas las luces
+    reflection: Vec3, // Ya multiplicada por la reflectividad
+    refraction: Vec3, // Ya multiplicada por la transparencia
+    shadow: Vec3,     // Luz que llega en promedio desde las luces, (1, 1, 1) sin sombra
+    alpha: f32,
+    behind: Vec3, // Lo que se ve a través de los texeles semitransparentes
+}
+
+impl Shading {
+    // Emisión más iluminación directa, sin rebotes
+    fn direct(&self) -> Vec3 {
+        self.emission + self.diffuse + self.specular
+    }
+
+    fn combine(&self) -> Vec3 {
+        let color = self.direct() + self.reflection + self.refraction;
+        // Mezcla alfa: lo que hay detrás se ve a través de los texeles semitransparentes
+        if self.alpha < 1.0 {
+            color * self.alpha + self.behind * (1.0 - self.alpha)
+        } else {
+            color
+        }
+    }
+}
+
+// Desenfoque de movimiento: cada rayo primario toma su propio instante dentro del
+// obturador, y el recorrido de cámara y los objetos animados se evalúan en ese instante
+#[derive(Clone, Copy)]
+pub struct Motion<'a> {
+    pub shutter: f32, // Segundos que el obturador queda abierto desde `time`
+    pub samples: u32, // Rayos por píxel, cada uno en un instante distinto
+    pub path: Option<&'a CameraPath>,
+}
+
+// Todo lo necesario para sombrear los píxeles de un cuadro
+pub struct RenderContext<'a> {
+    pub scene: &'a Scene,
+    pub camera: &'a Camera,
+    pub width: usize,
+    pub height: usize,
+    pub time: f32,
+    pub quality: Quality,
+    pub mode: RenderMode,
+    pub motion: Option<Motion<'a>>,
+    pub placements: &'a [Option<Placement>], // Por objeto; vacía si ninguno se mueve
+}
+
+impl RenderContext<'_> {
+    fn cast_ray(
+        &self,
+        ray_origin: &Vec3,
+        ray_direction: &Vec3,
+        depth: u32,
+        stats: &mut RayStats,
+    ) -> Vec3 {
+        if depth > self.quality.max_depth {
+            return SKY_COLOR.to_vec3(); // Color de fondo o "skybox"
+        }
+        if depth == 0 {
+            stats.primary += 1;
+        } else {
+            stats.secondary += 1;
+        }
+        stats.max_depth = stats.max_depth.max(depth);
+
+        let Some((_, closest_intersect)) = closest_opaque_intersect(
+            ray_origin,
+            ray_direction,
+            &self.scene.objects,
+            self.placements,
+            self.time,
+            stats,
+        ) else {
+            return self.apply_atmosphere(
+                SKY_COLOR.to_vec3(),
+                ray_origin,
+                ray_direction,
+                SKY_DISTANCE,
+                stats,
+            );
+        };
+
+        let distance = closest_intersect.distance;
+        let surface_color = self
+            .shade(closest_intersect, ray_origin, ray_direction, depth, stats)
+            .combine();
+
+        self.apply_atmosphere(surface_color, ray_origin, ray_direction, distance, stats)
+    }
+
+    // Iluminación local, reflexión, refracción y mezcla alfa del punto de impacto
+    fn shade(
+        &self,
+        mut closest_intersect: Intersect,
+        ray_origin: &Vec3,
+        ray_direction: &Vec3,
+        depth: u32,
+        stats: &mut RayStats,
+    ) -> Shading {
+        let time = self.time;
+        let bias = SURFACE_BIAS;
+        let alpha =
+            closest_intersect
+                .material
+                .get_alpha(closest_intersect.u, closest_intersect.v, time);
+        let surface_point = closest_intersect.point;
+        let pass_through_origin = surface_point + ray_direction * bias;
+        closest_intersect.point += closest_intersect.normal * bias;
+
+        let normal = closest_intersect.normal;
+
+        let diffuse_color = closest_intersect.material.get_diffuse_color(
+            closest_intersect.u,
+            closest_intersect.v,
+            time,
+        );
+        let direct = self.shade_direct(&closest_intersect, diffuse_color, ray_origin, stats);
+
+        let reflectivity = closest_intersect.material.albedo[2];
+        let transparency = closest_intersect.material.albedo[3];
+
+        let mut reflection = Vec3::zeros();
+        if reflectivity > 0.0 {
+            let reflect_dir = reflect(ray_direction, &normal).normalize();
+            let reflect_origin = offset_origin(&surface_point, &normal, &reflect_dir);
+            let reflect_color = self.cast_ray(&reflect_origin, &reflect_dir, depth + 1, stats);
+            reflection = reflect_color * reflectivity;
+        }
+
+        let mut refraction = Vec3::zeros();
+        if transparency > 0.0 {
+            let refract_dir = refract(
+                ray_direction,
+                &normal,
+                closest_intersect.material.refractive_index,
+            );
+            // Del otro lado de la superficie, salvo con reflexión interna total
+            let refract_origin = offset_origin(&surface_point, &normal, &refract_dir);
+            let refract_color = self.cast_ray(&refract_origin, &refract_dir, depth + 1, stats);
+            refraction = refract_color * transparency;
+        }
+
+        let behind = if alpha < 1.0 {
+            self.cast_ray(&pass_through_origin, ray_direction, depth + 1, stats)
+        } else {
+            Vec3::zeros()
+        };
+
+        Shading {
+            reflection,
+            refraction,
+            alpha,
+            behind,
+            ..direct
+        }
+    }
+
+    // Emisión e iluminación directa de las luces, sin lanzar rayos de rebote. El punto de
+    // `hit` ya está separado de la superficie; `surface_color` es el color difuso
+    fn shade_direct(
+        &self,
+        hit: &Intersect,
+        surface_color: Color,
+        ray_origin: &Vec3,
+        stats: &mut RayStats,
+    ) -> Shading {
+        let normal = hit.normal;
+
+        // Color propio del material (lava, pantallas...), independiente de las luces
+        let surface_color = surface_color.to_vec3();
+        let emission = surface_color * hit.material.emission;
+        let mut diffuse_light = Vec3::zeros();
+        let mut specular_light = Vec3::zeros();
+        let mut shadow = Vec3::zeros();
+
+        // Iterar sobre todas las fuentes de luz
+        for light in &self.scene.lights {
+            // La vista previa ilumina todo como si nada hiciera sombra
+            let transmittance = if self.quality.shadows {
+                stats.shadow += 1;
+                cast_shadow(
+                    hit,
+                    light,
+                    &self.scene.objects,
+                    self.placements,
+                    self.time,
+                    stats,
+                )
+            } else {
+                Vec3::repeat(1.0)
+            };
+            shadow += transmittance;
+
+            let light_dir = (light.position - hit.point).normalize();
+
+            let diffuse_intensity = normal.dot(&light_dir).clamp(0.0, 1.0);
+            let diffuse = surface_color.component_mul(&transmittance)
+                * hit.material.albedo[0]
+                * diffuse_intensity
+                * light.intensity;
+
+            let view_dir = (ray_origin - hit.point).normalize();
+            let reflect_dir = reflect(&-light_dir, &normal);
+
+            let specular_intensity = view_dir
+                .dot(&reflect_dir)
+                .max(0.0)
+                .powf(hit.material.specular);
+            let specular = light.color.to_vec3().component_mul(&transmittance)
+                * hit.material.albedo[1]
+                * specular_intensity
+                * light.intensity;
+
+            diffuse_light += diffuse;
+            specular_light += specular;
+        }
+
+        Shading {
+            emission,
+            diffuse: diffuse_light,
+            specular: specular_light,
+            reflection: Vec3::zeros(),
+            refraction: Vec3::zeros(),
+            shadow: if self.scene.lights.is_empty() {
+                Vec3::repeat(1.0)
+            } else {
+                shadow / self.scene.lights.len() as f32
+            },
+            alpha: 1.0,
+            behind: Vec3::zeros(),
+        }
+    }
+
+    // Vistas de depuración que solo miran la primera superficie
+    fn inspect_surface(
+        &self,
+        ray_origin: &Vec3,
+        ray_direction: &Vec3,
+        stats: &mut RayStats,
+    ) -> Color {
+        stats.primary += 1;
+        let Some((object_index, hit)) = closest_opaque_intersect(
+            ray_origin,
+            ray_direction,
+            &self.scene.objects,
+            self.placements,
+            self.time,
+            stats,
+        ) else {
+            return Color::black();
+        };
+
+        match self.mode {
+            RenderMode::Depth => {
+                let closeness = 1.0 - (hit.distance / DEPTH_RANGE).min(1.0);
+                Color::from_vec3(&Vec3::repeat(closeness * 255.0))
+            }
+            RenderMode::WorldPosition => {
+                Color::from_vec3(&((hit.point / POSITION_RANGE).add_scalar(0.5) * 255.0))
+            }
+            RenderMode::Normals => {
+                Color::from_vec3(&((hit.normal + Vec3::repeat(1.0)) * 0.5 * 255.0))
+            }
+            RenderMode::Uvs => Color::from_vec3(&(Vec3::new(hit.u, hit.v, 0.0) * 255.0)),
+            RenderMode::MaterialId => Color::from_id(hit.material.id()),
+            RenderMode::ObjectId => Color::from_id(object_index as u64),
+            RenderMode::ReflectionOnly => Color::from_vec3(
+                &self
+                    .shade(hit, ray_origin, ray_direction, 0, stats)
+                    .reflection,
+            ),
+            _ => {
+                // Solo luces y sombras: no hace falta lanzar reflejos ni refracciones
+                let surface_color = if self.mode == RenderMode::LitNormals {
+                    Color::from_vec3(&((hit.normal + Vec3::repeat(1.0)) * 0.5 * 255.0))
+                } else {
+                    hit.material.get_diffuse_color(hit.u, hit.v, self.time)
+                };
+                let mut hit = hit;
+                hit.point += hit.normal * SURFACE_BIAS;
+                let shading = self.shade_direct(&hit, surface_color, ray_origin, stats);
+                match self.mode {
+                    RenderMode::ShadowOnly => Color::from_vec3(&(shading.shadow * 255.0)),
+                    _ => Color::from_vec3(&shading.direct()),
+                }
+            }
+        }
+    }
+
+    // Niebla por distancia y altura, más la luz dispersada por el volumen si existe
+    fn apply_atmosphere(
+        &self,
+        color: Vec3,
+        ray_origin: &Vec3,
+        ray_direction: &Vec3,
+        distance: f32,
+        stats: &mut RayStats,
+    ) -> Vec3 {
+        let Some(fog) = &self.scene.fog else {
+            return color;
+        };
+
+        let mut color = fog.apply(color, ray_origin, ray_direction, distance);
+
+        if let Some(volume) = fog.volume.as_ref().filter(|_| self.quality.volumetrics) {
+            let in_scattered =
+                self.volume_in_scattering(volume, ray_origin, ray_direction, distance, stats);
+            color = color * volume.transmittance(distance) + in_scattered;
+        }
+
+        color
+    }
+
+    // Dispersión simple: marchar por el rayo sumando la luz visible desde cada muestra
+    fn volume_in_scattering(
+        &self,
+        volume: &Volume,
+        ray_origin: &Vec3,
+        ray_direction: &Vec3,
+        distance: f32,
+        stats: &mut RayStats,
+    ) -> Vec3 {
+        let march_distance = distance.min(volume.max_distance);
+        let step = march_distance / volume.steps as f32;
+        let mut scattered = Vec3::zeros();
+
+        for i in 0..volume.steps {
+            let t = (i as f32 + 0.5) * step;
+            let sample_point = ray_origin + ray_direction * t;
+            let view_transmittance = volume.transmittance(t);
+
+            for light in &self.scene.lights {
+                stats.shadow += 1;
+                let light_dir = (light.position - sample_point).normalize();
+                let visibility = light_transmittance(
+                    &sample_point,
+                    light,
+                    &self.scene.objects,
+                    self.placements,
+                    self.time,
+                    stats,
+                );
+                let phase = volume.phase(ray_direction.dot(&light_dir));
+                let light_color = light.color.to_vec3() * light.intensity;
+
+                scattered += visibility.component_mul(&light_color)
+                    * (phase * volume.scattering * view_transmittance * step);
+            }
+        }
+
+        scattered
+    }
+
+    pub fn render_pixel(
+        &self,
+        x: usize,
+        y: usize,
+        rng: &mut impl Rng,
+        stats: &mut RayStats,
+    ) -> Color {
+        let (color, samples) = match self.quality.adaptive {
+            Some(sampling) => self.sample_adaptive(x, y, &sampling, rng, stats),
+            None => self.sample_uniform(x, y, rng, stats),
+        };
+
+        if self.mode == RenderMode::SampleCount {
+            // Vista de depuración: azul pocas muestras, rojo el máximo
+            let max_samples = match self.quality.adaptive {
+                Some(sampling) => sampling.max_samples,
+                None => self.quality.lens_samples,
+            };
+            return Color::heatmap(samples as f32 / max_samples.max(1) as f32);
+        }
+        color
+    }
+
+    // Rayos por píxel fijos; devuelve el color y cuántas muestras se usaron
+    fn sample_uniform(
+        &self,
+        x: usize,
+        y: usize,
+        rng: &mut impl Rng,
+        stats: &mut RayStats,
+    ) -> (Color, u32) {
+        let camera = self.camera;
+        // Cada muestra cae en un instante distinto del obturador, aunque no haya apertura
+        if let Some(motion) = self.motion {
+            let samples = motion.samples.max(1);
+            let mut accumulated = Vec3::zeros();
+            for _ in 0..samples {
+                accumulated += self.jittered_sample(x, y, rng, stats);
+            }
+            return (Color::from_vec3(&(accumulated / samples as f32)), samples);
+        }
+
+        // Una cámara estenopeica no necesita más que un rayo por píxel
+        if camera.aperture <= 0.0 || self.quality.lens_samples <= 1 {
+            let (ray_origin, ray_direction) =
+                camera.ray_for_pixel(x, y, self.width, self.height, (0.5, 0.5), (0.0, 0.0));
+            let color = Color::from_vec3(&self.sample_ray(&ray_origin, &ray_direction, stats));
+            return (color, 1);
+        }
+
+        // Promediar muestras con posición en el píxel y punto del lente aleatorios
+        let mut accumulated = Vec3::zeros();
+        for _ in 0..self.quality.lens_samples {
+            accumulated += self.jittered_sample(x, y, rng, stats);
+        }
+        let color = Color::from_vec3(&(accumulated / self.quality.lens_samples as f32));
+        (color, self.quality.lens_samples)
+    }
+
+    // Muestras hasta que el error estimado del píxel sea aceptable
+    fn sample_adaptive(
+        &self,
+        x: usize,
+        y: usize,
+        sampling: &AdaptiveSampling,
+        rng: &mut impl Rng,
+        stats: &mut RayStats,
+    ) -> (Color, u32) {
+        let mut estimate = PixelEstimate::new();
+        while !sampling.is_converged(&estimate) {
+            estimate.add(&self.jittered_sample(x, y, rng, stats));
+        }
+        (Color::from_vec3(&estimate.mean()), estimate.count())
+    }
+
+    // Un rayo por un punto aleatorio del píxel y, con apertura, del lente. Con desenfoque
+    // de movimiento, en un instante aleatorio del obturador
+    fn jittered_sample(
+        &self,
+        x: usize,
+        y: usize,
+        rng: &mut impl Rng,
+        stats: &mut RayStats,
+    ) -> Vec3 {
+        if let Some(motion) = self.motion {
+            let time = self.time + rng.gen::<f32>() * motion.shutter;
+
+            let mut camera = self.camera.clone();
+            if let Some(path) = motion.path {
+                path.apply(&mut camera, time);
+            }
+            return SAMPLE_PLACEMENTS.with(|placements| {
+                let mut placements = placements.borrow_mut();
+                self.scene.place_objects(time, &mut placements);
+                let instant = RenderContext {
+                    camera: &camera,
+                    time,
+                    motion: None,
+                    placements: &placements,
+                    ..*self
+                };
+                instant.jittered_sample(x, y, rng, stats)
+            });
+        }
+
+        let offset = (rng.gen::<f32>(), rng.gen::<f32>());
+        let lens = if self.camera.aperture > 0.0 {
+            self.camera.sample_lens(rng.gen(), rng.gen())
+        } else {
+            (0.0, 0.0)
+        };
+        let (ray_origin, ray_direction) =
+            self.camera
+                .ray_for_pixel(x, y, self.width, self.height, offset, lens);
+        self.sample_ray(&ray_origin, &ray_direction, stats)
+    }
+
+    // Color de un rayo primario según el modo de render; suma sus rayos a `stats`
+    fn sample_ray(&self, ray_origin: &Vec3, ray_direction: &Vec3, stats: &mut RayStats) -> Vec3 {
+        let mut ray_stats = RayStats::default();
+        let color = match self.mode {
+            // La imagen de 8 bits recorta cada muestra antes de promediar
+            RenderMode::Beauty | RenderMode::SampleCount => {
+                Color::from_vec3(&self.cast_ray(ray_origin, ray_direction, 0, &mut ray_stats))
+            }
+            RenderMode::BounceCount => {
+                self.cast_ray(ray_origin, ray_direction, 0, &mut ray_stats);
+                Color::heatmap(ray_stats.max_depth as f32 / self.quality.max_depth.max(1) as f32)
+            }
+            RenderMode::RayCost => {
+                self.cast_ray(ray_origin, ray_direction, 0, &mut ray_stats);
+                Color::heatmap(ray_stats.total() as f32 / RAY_COST_SCALE)
+            }
+            _ => self.inspect_surface(ray_origin, ray_direction, &mut ray_stats),
+        };
+        *stats += ray_stats;
+        color.to_vec3()
+    }
+
+    // Pases para composición; los de color promedian las mismas muestras de lente que la
+    // imagen final y los geométricos usan el rayo central del píxel
+    pub fn render_passes(&self) -> RenderPasses {
+        let pixels = (0..self.width * self.height)
+            .into_par_iter()
+            .map_init(rand::thread_rng, |rng, index| {
+                self.pixel_passes(index % self.width, index / self.width, rng)
+            })
+            .collect();
+
+        RenderPasses {
+            width: self.width,
+            height: self.height,
+            pixels,
+        }
+    }
+
+    fn pixel_passes(&self, x: usize, y: usize, rng: &mut impl Rng) -> PixelPasses {
+        let mut passes = PixelPasses::new();
+
+        let (ray_origin, ray_direction) =
+            self.camera
+                .ray_for_pixel(x, y, self.width, self.height, (0.5, 0.5), (0.0, 0.0));
+        match closest_opaque_intersect(
+            &ray_origin,
+            &ray_direction,
+            &self.scene.objects,
+            self.placements,
+            self.time,
+            &mut RayStats::default(),
+        ) {
+            Some((object_index, hit)) => {
+                passes.depth = hit.distance;
+                passes.normal = hit.normal;
+                passes.object_id = (object_index + 1) as f32;
+            }
+            None => passes.depth = SKY_DISTANCE,
+        }
+
+        if self.camera.aperture <= 0.0 || self.quality.lens_samples <= 1 {
+            self.add_surface_passes(&ray_origin, &ray_direction, 1.0, &mut passes);
+        } else {
+            let weight = 1.0 / self.quality.lens_samples as f32;
+            for _ in 0..self.quality.lens_samples {
+                let offset = (rng.gen::<f32>(), rng.gen::<f32>());
+                let lens = self.camera.sample_lens(rng.gen(), rng.gen());
+                let (ray_origin, ray_direction) =
+                    self.camera
+                        .ray_for_pixel(x, y, self.width, self.height, offset, lens);
+                self.add_surface_passes(&ray_origin, &ray_direction, weight, &mut passes);
+            }
+        }
+
+        passes
+    }
+
+    // Suma a `passes` los aportes de un rayo primario, multiplicados por `weight`
+    fn add_surface_passes(
+        &self,
+        ray_origin: &Vec3,
+        ray_direction: &Vec3,
+        weight: f32,
+        passes: &mut PixelPasses,
+    ) {
+        let mut stats = RayStats::default();
+        // Radiancia sin recortar llevada a [0, 1] para los pases en punto flotante
+        let to_pass = |radiance: Vec3| radiance * (weight / 255.0);
+
+        let Some((_, hit)) = closest_opaque_intersect(
+            ray_origin,
+            ray_direction,
+            &self.scene.objects,
+            self.placements,
+            self.time,
+            &mut stats,
+        ) else {
+            let sky = self.apply_atmosphere(
+                SKY_COLOR.to_vec3(),
+                ray_origin,
+                ray_direction,
+                SKY_DISTANCE,
+                &mut stats,
+            );
+            passes.beauty += to_pass(sky);
+            passes.shadow += Vec3::repeat(weight);
+            return;
+        };
+
+        let distance = hit.distance;
+        let shading = self.shade(hit, ray_origin, ray_direction, 0, &mut stats);
+        let beauty = self.apply_atmosphere(
+            shading.combine(),
+            ray_origin,
+            ray_direction,
+            distance,
+            &mut stats,
+        );
+
+        passes.beauty += to_pass(beauty);
+        passes.diffuse += to_pass(shading.diffuse);
+        passes.specular += to_pass(shading.specular);
+        passes.reflection += to_pass(shading.reflection);
+        passes.refraction += to_pass(shading.refraction);
+        passes.shadow += shading.shadow * weight;
+    }
+
+    // Albedo, normal y profundidad de la primera superficie en el centro de cada píxel
+    pub fn render_features(&self) -> FeatureBuffers {
+        let features: Vec<(Vec3, Vec3, f32)> = (0..self.width * self.height)
+            .into_par_iter()
+            .map(|index| {
+                let (x, y) = (index % self.width, index / self.width);
+                let (ray_origin, ray_direction) = self.camera.ray_for_pixel(
+                    x,
+                    y,
+                    self.width,
+                    self.height,
+                    (0.5, 0.5),
+                    (0.0, 0.0),
+                );
+                match closest_opaque_intersect(
+                    &ray_origin,
+                    &ray_direction,
+                    &self.scene.objects,
+                    self.placements,
+                    self.time,
+                    &mut RayStats::default(),
+                ) {
+                    Some((_, hit)) => {
+                        let albedo = hit.material.get_diffuse_color(hit.u, hit.v, self.time);
+                        (albedo.to_vec3() / 255.0, hit.normal, hit.distance)
+                    }
+                    None => (SKY_COLOR.to_vec3() / 255.0, -ray_direction, SKY_DISTANCE),
+                }
+            })
+            .collect();
+
+        FeatureBuffers {
+            width: self.width,
+            height: self.height,
+            albedo: features.iter().map(|feature| feature.0).collect(),
+            normal: features.iter().map(|feature| feature.1).collect(),
+            depth: features.iter().map(|feature| feature.2).collect(),
+        }
+    }
+
+    // Objeto bajo el centro del píxel, con el mismo rayo primario que el render
+    pub fn pick(&self, x: usize, y: usize) -> Option<(usize, Intersect)> {
+        let (ray_origin, ray_direction) =
+            self.camera
+                .ray_for_pixel(x, y, self.width, self.height, (0.5, 0.5), (0.0, 0.0));
+        closest_opaque_intersect(
+            &ray_origin,
+            &ray_direction,
+            &self.scene.objects,
+            self.placements,
+            self.time,
+            &mut RayStats::default(),
+        )
+    }
+
+    // Píxeles en los que el objeto `index` es la primera superficie visible
+    pub fn object_mask(&self, index: usize) -> Vec<bool> {
+        (0..self.width * self.height)
+            .into_par_iter()
+            .map(|pixel| {
+                let hit = self.pick(pixel % self.width, pixel / self.width);
+                matches!(hit, Some((object, _)) if object == index)
+            })
+            .collect()
+    }
+
+    // Renderiza el cuadro completo antes de volver; devuelve los rayos lanzados
+    pub fn render(&self, framebuffer: &mut Framebuffer) -> RayStats {
+        let mut job = RenderJob::new(
+            framebuffer.width,
+            framebuffer.height,
+            TILE_SIZE,
+            TileOrder::Rows,
+            self.time,
+        );
+        job.render_for(framebuffer, None, |tile| self.render_tile(tile));
+        job.stats
+    }
+
+    // Colores del mosaico fila por fila y los rayos que costaron
+    pub fn render_tile(&self, tile: &Tile) -> (Vec<Color>, RayStats) {
+        let mut rng = rand::thread_rng();
+        let mut stats = RayStats::default();
+        let mut colors = Vec::with_capacity(tile.width * tile.height);
+        for y in tile.y..tile.y + tile.height {
+            for x in tile.x..tile.x + tile.width {
+                colors.push(self.render_pixel(x, y, &mut rng, &mut stats));
+            }
+        }
+        (colors, stats)
+    }
+}
+
+// Renderiza el cuadro completo antes de volver
+pub fn render(
+    framebuffer: &mut Framebuffer,
+    scene: &Scene,
+    camera: &Camera,
+    time: f32, // Añadimos el tiempo para la animación
+    quality: Quality,
+    mode: RenderMode,
+) {
+    let context = RenderContext {
+        scene,
+        camera,
+        width: framebuffer.width,
+        height: framebuffer.height,
+        time,
+        quality,
+        mode,
+        motion: None,
+        placements: &[],
+    };
+    context.render(framebuffer);
+}
+
+fn cast_shadow(
+    intersect: &Intersect,
+    light: &Light,
+    objects: &[Box<dyn RayIntersect>],
+    placements: &[Option<Placement>],
+    time: f32,
+    stats: &mut RayStats,
+) -> Vec3 {
+    let bias = 0.001; // Use the same bias value as in cast_ray
+    let shadow_ray_origin = intersect.point + intersect.normal * bias;
+    light_transmittance(&shadow_ray_origin, light, objects, placements, time, stats)
+}
+
+// Luz (r, g, b) que llega desde `light` hasta `shadow_ray_origin`
+fn light_transmittance(
+    shadow_ray_origin: &Vec3,
+    light: &Light,
+    objects: &[Box<dyn RayIntersect>],
+    placements: &[Option<Placement>],
+    time: f32,
+    stats: &mut RayStats,
+) -> Vec3 {
+    let light_dir = (light.position - shadow_ray_origin).normalize();
+    let mut transmittance = Vec3::repeat(1.0);
+    let light_distance = (light.position - shadow_ray_origin).magnitude();
+
+    // Acumular la luz que atraviesa cada oclusor (vidrio, agua, texeles recortados)
+    for (index, object) in objects.iter().enumerate() {
+        let shadow_intersect = intersect_object(
+            index,
+            object.as_ref(),
+            placements,
+            shadow_ray_origin,
+            &light_dir,
+        );
+        stats.count_test(shadow_intersect.is_intersecting);
+        if !shadow_intersect.is_intersecting {
+            continue;
+        }
+
+        let opacity =
+            shadow_intersect
+                .material
+                .get_alpha(shadow_intersect.u, shadow_intersect.v, time);
+        if opacity <= ALPHA_CUTOFF {
+            continue;
+        }
+
+        let object_distance = shadow_intersect.distance;
+
+        // Calculamos la intensidad de la sombra basado en la distancia
+        let shadow_intensity = 1.0 - (object_distance / light_distance).min(1.0);
+        let surface_transmittance =
+            shadow_intersect
+                .material
+                .transmittance(shadow_intersect.u, shadow_intersect.v, time);
+        let occlusion = (Vec3::repeat(1.0) - surface_transmittance) * shadow_intensity;
+        transmittance.component_mul_assign(&(Vec3::repeat(1.0) - occlusion));
+
+        if transmittance.max() <= 0.0 {
+            break;
+        }
+    }
+
+    transmittance
+}
+
+fn refract(incident: &Vec3, normal: &Vec3, eta_t: f32) -> Vec3 {
+    let cosi = -incident.dot(normal).clamp(-1.0, 1.0);
+
+    let (n_cosi, eta, n_normal);
+
+    if cosi < 0.0 {
+        // El rayo está saliendo del objeto: la normal apunta hacia afuera, en su mismo sentido
+        n_cosi = -cosi;
+        eta = eta_t;
+        n_normal = -normal;
+    } else {
+        // El rayo está entrando en el objeto
+        n_cosi = cosi;
+        eta = 1.0 / eta_t;
+        n_normal = *normal;
+    }
+
+    let k = 1.0 - eta * eta * (1.0 - n_cosi * n_cosi);
+
+    if k < 0.0 {
+        // Reflexión interna total
+        reflect(incident, &n_normal)
+    } else {
+        eta * incident + (eta * n_cosi - k.sqrt()) * n_normal
+    }
+}
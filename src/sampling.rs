@@ -0,0 +1,85 @@
+use nalgebra_glm::Vec3;
+
+// Muestreo adaptativo: cada píxel recibe entre `min_samples` y `max_samples` rayos,
+// y deja de muestrear cuando el error estimado de su luminancia baja de `threshold`
+#[derive(Debug, Clone, Copy)]
+pub struct AdaptiveSampling {
+    pub min_samples: u32,
+    pub max_samples: u32,
+    pub threshold: f32, // Error estándar aceptado, en niveles de luminancia [0, 255]
+}
+
+impl AdaptiveSampling {
+    pub fn new(min_samples: u32, max_samples: u32, threshold: f32) -> Self {
+        // Hacen falta al menos dos muestras para estimar la varianza
+        let min_samples = min_samples.max(2);
+        AdaptiveSampling {
+            min_samples,
+            max_samples: max_samples.max(min_samples),
+            threshold,
+        }
+    }
+
+    pub fn is_converged(&self, estimate: &PixelEstimate) -> bool {
+        let count = estimate.count();
+        count >= self.max_samples
+            || (count >= self.min_samples && estimate.standard_error() <= self.threshold)
+    }
+}
+
+impl Default for AdaptiveSampling {
+    fn default() -> Self {
+        AdaptiveSampling::new(4, 64, 1.5)
+    }
+}
+
+// Media del color y varianza de la luminancia de un píxel, acumuladas con Welford
+#[derive(Debug, Clone, Copy)]
+pub struct PixelEstimate {
+    count: u32,
+    sum: Vec3,
+    mean_luminance: f32,
+    squared_deviations: f32,
+}
+
+impl PixelEstimate {
+    pub fn new() -> Self {
+        PixelEstimate {
+            count: 0,
+            sum: Vec3::zeros(),
+            mean_luminance: 0.0,
+            squared_deviations: 0.0,
+        }
+    }
+
+    pub fn add(&mut self, color: &Vec3) {
+        self.count += 1;
+        self.sum += color;
+
+        let luminance = 0.2126 * color.x + 0.7152 * color.y + 0.0722 * color.z;
+        let delta = luminance - self.mean_luminance;
+        self.mean_luminance += delta / self.count as f32;
+        self.squared_deviations += delta * (luminance - self.mean_luminance);
+    }
+
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    pub fn mean(&self) -> Vec3 {
+        if self.count == 0 {
+            Vec3::zeros()
+        } else {
+            self.sum / self.count as f32
+        }
+    }
+
+    // Error estándar de la media: cuánto puede cambiar el píxel con más muestras
+    pub fn standard_error(&self) -> f32 {
+        if self.count < 2 {
+            return f32::INFINITY;
+        }
+        let variance = self.squared_deviations / (self.count - 1) as f32;
+        (variance / self.count as f32).sqrt()
+    }
+}
@@ -0,0 +1,851 @@
+use crate::animation::{Target, Timeline};
+use crate::color::Color;
+use crate::cuboid::Cuboid;
+use crate::fog::Fog;
+use crate::light::Light;
+use crate::material::Material;
+use crate::ray_intersect::RayIntersect;
+use crate::sphere::Sphere;
+use crate::text_format::{data_lines, invalid_line, parse_floats};
+use crate::texture::Texture;
+use nalgebra_glm::Vec3;
+use once_cell::sync::Lazy;
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::sync::Arc;
+
+// Distancia bajo la cual dos bloques se consideran en la misma celda
+const BLOCK_EPSILON: f32 = 0.01;
+
+static LADRILLOS: Lazy<Arc<Texture>> = Lazy::new(|| builtin_texture("assets/ladrillos.png"));
+static WATER: Lazy<Arc<Texture>> = Lazy::new(|| builtin_texture("assets/WATER.jpg"));
+static LADRILLOS_NEGROS: Lazy<Arc<Texture>> =
+    Lazy::new(|| builtin_texture("assets/ladrillos_negros.png"));
+static SUELO: Lazy<Arc<Texture>> = Lazy::new(|| builtin_texture("assets/suelo.png"));
+static BRICKS: Lazy<Arc<Texture>> = Lazy::new(|| builtin_texture("assets/Bricks.png"));
+static LAVA: Lazy<Arc<Texture>> = Lazy::new(|| builtin_texture("assets/Lava.jpg"));
+
+// Las texturas del diorama vienen con el proyecto, así que sin ellas no hay escena
+fn builtin_texture(file_path: &str) -> Arc<Texture> {
+    Arc::new(
+        Texture::new(file_path)
+            .unwrap_or_else(|e| panic!("No se pudo cargar {}: {}", file_path, e)),
+    )
+}
+
+// Centro y tamaño de un objeto animado en un instante, sin aplicarlos a la escena
+#[derive(Debug, Clone, Copy)]
+pub struct Placement {
+    pub center: Vec3,
+    pub size: Vec3,
+}
+
+pub struct Scene {
+    pub objects: Vec<Box<dyn RayIntersect>>,
+    pub lights: Vec<Light>,
+    pub fog: Option<Fog>,
+    pub timeline: Timeline,
+    pub materials: Vec<Material>, // Paleta para los bloques que se agregan al editar
+}
+
+impl Scene {
+    pub fn new(objects: Vec<Box<dyn RayIntersect>>, lights: Vec<Light>) -> Self {
+        let materials = palette(&objects);
+        Scene {
+            objects,
+            lights,
+            fog: None,
+            timeline: Timeline::default(),
+            materials,
+        }
+    }
+
+    // Agrega un bloque unitario con el material `material` de la paleta, salvo que
+    // ya haya otro objeto centrado en el mismo lugar
+    pub fn add_block(&mut self, center: Vec3, material: usize) -> bool {
+        let Some(material) = self.materials.get(material) else {
+            return false;
+        };
+        let occupied = self
+            .objects
+            .iter()
+            .any(|object| nalgebra_glm::distance(&object.center(), &center) < BLOCK_EPSILON);
+        if occupied {
+            return false;
+        }
+        self.objects.push(Box::new(Cuboid::new(
+            center,
+            1.0,
+            1.0,
+            1.0,
+            material.clone(),
+        )));
+        true
+    }
+
+    pub fn remove_object(&mut self, index: usize) {
+        if index < self.objects.len() {
+            self.objects.remove(index);
+            self.timeline.remove_object(index);
+        }
+    }
+
+    // Formato de texto: una línea por material y una por objeto
+    //   material nombre r g b especular albedo0 albedo1 albedo2 albedo3 índice emisión
+    //            tinte.r tinte.g tinte.b textura [u v]
+    //   cuboide material centro.x centro.y centro.z ancho alto profundidad
+    //   esfera material centro.x centro.y centro.z radio
+    // (el material va en una sola línea). El tinte multiplica la textura; la textura es `-`
+    // si no hay y `u v` es la velocidad de animación opcional. Los objetos
+    // cuyo material se editó por separado guardan su propia copia con otro nombre.
+    pub fn save_objects(&self, file_path: &str) -> io::Result<()> {
+        // (nombre en el archivo, valores) de cada material distinto
+        let mut entries: Vec<(String, String)> = Vec::new();
+        let mut entry_name = |material: &Material| -> String {
+            let values = material_values(material);
+            let base = file_name(material, entries.len());
+            let mut name = base.clone();
+            let mut copy = 1;
+            while let Some((_, known)) = entries.iter().find(|(known, _)| *known == name) {
+                if *known == values {
+                    return name;
+                }
+                copy += 1;
+                name = format!("{}_{}", base, copy);
+            }
+            entries.push((name.clone(), values));
+            name
+        };
+
+        for material in &self.materials {
+            entry_name(material);
+        }
+        let mut lines = String::new();
+        for object in &self.objects {
+            let (center, size) = (object.center(), object.size());
+            lines.push_str(&format!(
+                "{} {} {} {} {}",
+                object.kind(),
+                entry_name(object.material()),
+                center.x,
+                center.y,
+                center.z
+            ));
+            if object.kind() == "esfera" {
+                lines.push_str(&format!(" {}\n", size.x));
+            } else {
+                lines.push_str(&format!(" {} {} {}\n", size.x, size.y, size.z));
+            }
+        }
+
+        let mut contents = String::from(
+            "# material nombre r g b especular albedo0 albedo1 albedo2 albedo3 índice emisión textura [u v]\n\
+             # cuboide material centro.x centro.y centro.z ancho alto profundidad\n\
+             # esfera material centro.x centro.y centro.z radio\n",
+        );
+        for (name, values) in &entries {
+            contents.push_str(&format!("material {} {}\n", name, values));
+        }
+        contents.push_str(&lines);
+        fs::write(file_path, contents)
+    }
+
+    // Reemplaza los objetos y la paleta por los del archivo. La línea de tiempo se
+    // descarta porque sus pistas apuntan a los objetos anteriores; devuelve cuántas pistas
+    // se descartaron para poder avisarlo
+    pub fn load_objects(&mut self, file_path: &str) -> io::Result<usize> {
+        let mut materials: Vec<Material> = Vec::new();
+        let mut textures: HashMap<String, Arc<Texture>> = HashMap::new();
+        let mut objects: Vec<Box<dyn RayIntersect>> = Vec::new();
+
+        for (number, line) in data_lines(&fs::read_to_string(file_path)?) {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields[0] == "material" {
+                if fields.len() != 16 && fields.len() != 18 {
+                    return Err(invalid_line(number, "se esperaban 15 o 17 valores"));
+                }
+                let values = parse_floats(number, &fields[2..15])?;
+                let texture = match fields[15] {
+                    "-" => None,
+                    path => match textures.get(path) {
+                        Some(texture) => Some(texture.clone()),
+                        None => {
+                            let texture = Arc::new(Texture::new(path).map_err(|e| {
+                                invalid_line(number, &format!("no se pudo cargar {}: {}", path, e))
+                            })?);
+                            textures.insert(path.to_string(), texture.clone());
+                            Some(texture)
+                        }
+                    },
+                };
+                let animation_speed = match fields.get(16..18) {
+                    Some(speed) => {
+                        let speed = parse_floats(number, speed)?;
+                        Some((speed[0], speed[1]))
+                    }
+                    None => None,
+                };
+                let mut material = Material::new(
+                    Color::new(values[0] as i32, values[1] as i32, values[2] as i32),
+                    values[3],
+                    [values[4], values[5], values[6], values[7]],
+                    values[8],
+                    texture,
+                    animation_speed,
+                )
+                .with_name(fields[1]);
+                material.emission = values[9];
+                material.tint = Color::new(values[10] as i32, values[11] as i32, values[12] as i32);
+                materials.push(material);
+                continue;
+            }
+
+            let expected = match fields[0] {
+                "cuboide" => 8,
+                "esfera" => 6,
+                other => {
+                    return Err(invalid_line(
+                        number,
+                        &format!("tipo desconocido: {}", other),
+                    ));
+                }
+            };
+            if fields.len() != expected {
+                return Err(invalid_line(
+                    number,
+                    &format!("se esperaban {} valores", expected - 1),
+                ));
+            }
+            let material = materials
+                .iter()
+                .find(|material| material.name == fields[1])
+                .ok_or_else(|| {
+                    invalid_line(number, &format!("material desconocido: {}", fields[1]))
+                })?
+                .clone();
+            let values = parse_floats(number, &fields[2..])?;
+            let center = Vec3::new(values[0], values[1], values[2]);
+            if fields[0] == "esfera" {
+                objects.push(Box::new(Sphere::new(center, values[3], material)));
+            } else {
+                objects.push(Box::new(Cuboid::new(
+                    center, values[3], values[4], values[5], material,
+                )));
+            }
+        }
+
+        let dropped = self.timeline.tracks.len();
+        self.objects = objects;
+        self.materials = materials;
+        self.timeline = Timeline::default();
+        Ok(dropped)
+    }
+
+    // Aplica los valores de la línea de tiempo en `time` a objetos, materiales y luces
+    pub fn animate(&mut self, time: f32) {
+        for track in &self.timeline.tracks {
+            let Some(value) = track.value(time) else {
+                continue;
+            };
+            match track.target {
+                Target::ObjectPosition(index) => {
+                    if let Some(object) = self.objects.get_mut(index) {
+                        object.set_center(value);
+                    }
+                }
+                Target::ObjectSize(index) => {
+                    if let Some(object) = self.objects.get_mut(index) {
+                        object.set_size(value);
+                    }
+                }
+                Target::MaterialColor(index) => {
+                    if let Some(object) = self.objects.get_mut(index) {
+                        // Las texturas ignoran el color difuso, así que se tiñen
+                        let material = object.material_mut();
+                        let color = Color::from_vec3(&value);
+                        if material.texture.is_some() {
+                            material.tint = color;
+                        } else {
+                            material.diffuse = color;
+                        }
+                    }
+                }
+                Target::MaterialEmission(index) => {
+                    if let Some(object) = self.objects.get_mut(index) {
+                        object.material_mut().emission = value.x.max(0.0);
+                    }
+                }
+                Target::MaterialTransparency(index) => {
+                    if let Some(object) = self.objects.get_mut(index) {
+                        object.material_mut().albedo[3] = value.x.clamp(0.0, 1.0);
+                    }
+                }
+                Target::LightPosition(index) => {
+                    if let Some(light) = self.lights.get_mut(index) {
+                        light.position = value;
+                    }
+                }
+                Target::LightColor(index) => {
+                    if let Some(light) = self.lights.get_mut(index) {
+                        light.color = Color::from_vec3(&value);
+                    }
+                }
+                Target::LightIntensity(index) => {
+                    if let Some(light) = self.lights.get_mut(index) {
+                        light.intensity = value.x.max(0.0);
+                    }
+                }
+            }
+        }
+    }
+
+    // Centro y tamaño en `time` de los objetos que mueve la línea de tiempo, sin modificar
+    // la escena: el desenfoque de movimiento los evalúa en el instante de cada rayo.
+    // `placements` queda indexado por objeto, o vacío si la línea de tiempo no mueve
+    // ninguno; se reutiliza para no reservar memoria en cada rayo
+    pub fn place_objects(&self, time: f32, placements: &mut Vec<Option<Placement>>) {
+        placements.clear();
+        for track in &self.timeline.tracks {
+            let index = match track.target {
+                Target::ObjectPosition(index) | Target::ObjectSize(index) => index,
+                _ => continue,
+            };
+            let (Some(object), Some(value)) = (self.objects.get(index), track.value(time)) else {
+                continue;
+            };
+            if placements.is_empty() {
+                placements.resize(self.objects.len(), None);
+            }
+            let placement = placements[index].get_or_insert(Placement {
+                center: object.center(),
+                size: object.size(),
+            });
+            if let Target::ObjectSize(_) = track.target {
+                placement.size = value;
+            } else {
+                placement.center = value;
+            }
+        }
+    }
+
+    // Coloca las luces según el ángulo del ciclo de día y noche
+    pub fn set_sun_angle(&mut self, angle: f32) {
+        for light in &mut self.lights {
+            light.position.x = 10.0 * angle.cos();
+            light.position.z = 10.0 * angle.sin();
+            light.position.y = 10.0 * angle.sin();
+
+            if light.position.y > 0.0 {
+                let intensity_factor = (light.position.y / 10.0).clamp(0.0, 1.0);
+                light.color = Color::new(
+                    (255.0 * intensity_factor) as i32,
+                    (223.0 * intensity_factor) as i32,
+                    (191.0 * intensity_factor) as i32,
+                );
+                light.intensity = 2.0 * intensity_factor;
+            } else {
+                let intensity_factor = (-light.position.y / 10.0).clamp(0.0, 1.0);
+                light.color = Color::new(
+                    (64.0 * intensity_factor) as i32,
+                    (96.0 * intensity_factor) as i32,
+                    (255.0 * intensity_factor) as i32,
+                );
+                light.intensity = 0.5 * intensity_factor;
+            }
+        }
+    }
+}
+
+// Materiales distintos (por nombre) de los objetos, en orden de aparición
+fn palette(objects: &[Box<dyn RayIntersect>]) -> Vec<Material> {
+    let mut materials: Vec<Material> = Vec::new();
+    for object in objects {
+        let material = object.material();
+        if !materials.iter().any(|known| known.name == material.name) {
+            materials.push(material.clone());
+        }
+    }
+    materials
+}
+
+// Valores del material en el archivo de escena, después del nombre
+fn material_values(material: &Material) -> String {
+    let [a0, a1, a2, a3] = material.albedo;
+    let texture = material
+        .texture
+        .as_ref()
+        .map_or("-", |texture| texture.path.as_str());
+    let mut values = format!(
+        "{} {} {} {} {} {} {} {} {} {} {} {} {} {}",
+        material.diffuse.r,
+        material.diffuse.g,
+        material.diffuse.b,
+        material.specular,
+        a0,
+        a1,
+        a2,
+        a3,
+        material.refractive_index,
+        material.emission,
+        material.tint.r,
+        material.tint.g,
+        material.tint.b,
+        texture
+    );
+    if let Some((u, v)) = material.animation_speed {
+        values.push_str(&format!(" {} {}", u, v));
+    }
+    values
+}
+
+// Nombre del material en el archivo de escena: una sola palabra y nunca vacío
+fn file_name(material: &Material, index: usize) -> String {
+    if material.name.is_empty() {
+        format!("material_{}", index)
+    } else {
+        material
+            .name
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join("_")
+    }
+}
+
+// Diorama por defecto: agua, lava, suelo y columnas de ladrillos
+pub fn diorama() -> Scene {
+    // Definir múltiples luces
+    let light1 = Light::new(Vec3::new(5.0, 10.0, 5.0), Color::new(255, 255, 255), 2.0);
+
+    let _light2 = Light::new(
+        Vec3::new(5.0, 10.0, -5.0),
+        Color::new(255, 100, 100), // Luz rojiza
+        1.0,
+    );
+
+    let lights = vec![light1];
+
+    let material_con_textura = Material::new(
+        Color::new(255, 255, 255),
+        500.0,
+        [0.8, 0.2, 0.0, 0.0],
+        1.5,
+        Some(LADRILLOS.clone()),
+        None,
+    )
+    .with_name("ladrillos");
+
+    let water = Material::new(
+        Color::new(200, 200, 255),
+        125.0,
+        [0.0, 0.5, 0.7, 0.5], // Difusa, especular, reflejo, transparencia
+        1.33,                 // Índice de refracción del agua
+        Some(WATER.clone()),
+        Some((50.0, 50.0)),
+    )
+    .with_name("agua");
+
+    let lava = Material::new(
+        Color::new(255, 100, 0),
+        500.0,
+        [0.8, 0.2, 0.0, 0.0], // Difusa, especular, reflejo, transparencia
+        1.5,
+        Some(LAVA.clone()),
+        Some((25.0, 50.0)),
+    )
+    .with_name("lava");
+
+    let ladrillos_neg = Material::new(
+        Color::new(255, 255, 255),
+        500.0,
+        [0.8, 0.2, 0.0, 0.1], // Difusa, especular, reflejo, transparencia
+        1.5,
+        Some(LADRILLOS_NEGROS.clone()),
+        None,
+    )
+    .with_name("ladrillos_negros");
+
+    let suelo = Material::new(
+        Color::new(128, 128, 128), // Color gris para el suelo
+        100.0,                     // Menor reflectividad que ladrillos_neg
+        [0.6, 0.3, 0.1, 0.0],      // Coeficientes de reflexión diferentes
+        1.0,                       // Índice de refracción diferente
+        Some(SUELO.clone()),       // Textura del suelo
+        None,
+    )
+    .with_name("suelo");
+
+    let texture_bricks = Material::new(
+        Color::new(255, 255, 255), // Color blanco para los ladrillos
+        250.0,                     // Alta reflectividad
+        [0.9, 0.3, 0.0, 0.0],      // Coeficientes de reflexión
+        1.0,                       // Índice de refracción
+        Some(BRICKS.clone()),      // Textura de los ladrillos
+        None,
+    )
+    .with_name("bricks");
+
+    // Agua compuesta por 4 cubos
+    let cuboid1 = Cuboid::new(
+        Vec3::new(-1.0, 0.0, 0.0), // Centro del cubo
+        1.0,                       // Ancho del cubo
+        1.0,                       // Altura del cubo
+        1.0,                       // Profundidad del cubo
+        water.clone(),             // Material de caucho
+    );
+
+    let cuboid2 = Cuboid::new(
+        Vec3::new(-2.0, 0.0, 0.0), // Centro del cubo
+        1.0,                       // Ancho del cubo
+        1.0,                       // Altura del cubo
+        1.0,                       // Profundidad del cubo
+        water.clone(),             // Material de caucho
+    );
+
+    let cuboid3 = Cuboid::new(
+        Vec3::new(-1.0, 0.0, 1.0), // Centro del cubo
+        1.0,                       // Ancho del cubo
+        1.0,                       // Altura del cubo
+        1.0,                       // Profundidad del cubo
+        water.clone(),             // Material de caucho
+    );
+
+    let cuboid4 = Cuboid::new(
+        Vec3::new(-2.0, 0.0, 1.0), // Centro del cubo
+        1.0,                       // Ancho del cubo
+        1.0,                       // Altura del cubo
+        1.0,                       // Profundidad del cubo
+        water.clone(),             // Material de caucho
+    );
+
+    // Columnas de ladrillos
+    let cuboid5 = Cuboid::new(
+        Vec3::new(0.0, 0.0, -1.0), // Centro del cubo
+        1.0,                       // Ancho del cubo
+        1.0,                       // Altura del cubo
+        1.0,                       // Profundidad del cubo
+        ladrillos_neg.clone(),     // Material de caucho
+    );
+
+    let cuboid6: Cuboid = Cuboid::new(
+        Vec3::new(0.0, 1.0, -1.0), // Centro del cubo
+        1.0,                       // Ancho del cubo
+        1.0,                       // Altura del cubo
+        1.0,                       // Profundidad del cubo
+        ladrillos_neg.clone(),     // Material de caucho
+    );
+
+    let cuboid7: Cuboid = Cuboid::new(
+        Vec3::new(0.0, 2.0, -1.0), // Centro del cubo
+        0.5,                       // Ancho del cubo
+        0.5,                       // Altura del cubo
+        0.5,                       // Profundidad del cubo
+        ladrillos_neg.clone(),     // Material de caucho
+    );
+
+    let cuboid8: Cuboid = Cuboid::new(
+        Vec3::new(0.0, 0.0, 2.0), // Centro del cubo
+        1.0,                      // Ancho del cubo
+        1.0,                      // Altura del cubo
+        1.0,                      // Profundidad del cubo
+        ladrillos_neg.clone(),    // Material de caucho
+    );
+
+    let cuboid9: Cuboid = Cuboid::new(
+        Vec3::new(0.0, 1.0, 2.0), // Centro del cubo
+        1.0,                      // Ancho del cubo
+        1.0,                      // Altura del cubo
+        1.0,                      // Profundidad del cubo
+        ladrillos_neg.clone(),    // Material de caucho
+    );
+
+    let cuboid10: Cuboid = Cuboid::new(
+        Vec3::new(0.0, 2.0, 2.0), // Centro del cubo
+        0.5,                      // Ancho del cubo
+        0.5,                      // Altura del cubo
+        0.5,                      // Profundidad del cubo
+        ladrillos_neg.clone(),    // Material de caucho
+    );
+
+    let cuboid11: Cuboid = Cuboid::new(
+        Vec3::new(-3.0, 0.0, 2.0), // Centro del cubo
+        1.0,                       // Ancho del cubo
+        1.0,                       // Altura del cubo
+        1.0,                       // Profundidad del cubo
+        ladrillos_neg.clone(),     // Material de caucho
+    );
+
+    let cuboid12: Cuboid = Cuboid::new(
+        Vec3::new(-3.0, 1.0, 2.0), // Centro del cubo
+        1.0,                       // Ancho del cubo
+        1.0,                       // Altura del cubo
+        1.0,                       // Profundidad del cubo
+        ladrillos_neg.clone(),     // Material de caucho
+    );
+
+    let cuboid13: Cuboid = Cuboid::new(
+        Vec3::new(-3.0, 2.0, 2.0), // Centro del cubo
+        0.5,                       // Ancho del cubo
+        0.5,                       // Altura del cubo
+        0.5,                       // Profundidad del cubo
+        ladrillos_neg.clone(),     // Material de caucho
+    );
+
+    let cuboid14: Cuboid = Cuboid::new(
+        Vec3::new(-3.0, 0.0, -1.0), // Centro del cubo
+        1.0,                        // Ancho del cubo
+        1.0,                        // Altura del cubo
+        1.0,                        // Profundidad del cubo
+        ladrillos_neg.clone(),      // Material de caucho
+    );
+
+    let cuboid15: Cuboid = Cuboid::new(
+        Vec3::new(-3.0, 1.0, -1.0), // Centro del cubo
+        1.0,                        // Ancho del cubo
+        1.0,                        // Altura del cubo
+        1.0,                        // Profundidad del cubo
+        ladrillos_neg.clone(),      // Material de caucho
+    );
+
+    let cuboid16: Cuboid = Cuboid::new(
+        Vec3::new(-3.0, 2.0, -1.0), // Centro del cubo
+        0.5,                        // Ancho del cubo
+        0.5,                        // Altura del cubo
+        0.5,                        // Profundidad del cubo
+        ladrillos_neg.clone(),      // Material de caucho
+    );
+
+    // Suelo
+    let cuboid17: Cuboid = Cuboid::new(
+        Vec3::new(0.0, 0.0, 0.0), // Centro del cubo
+        1.0,                      // Ancho del cubo
+        1.0,                      // Altura del cubo
+        1.0,                      // Profundidad del cubo
+        texture_bricks.clone(),   // Material de caucho
+    );
+
+    let cuboid18: Cuboid = Cuboid::new(
+        Vec3::new(0.0, 0.0, 1.0), // Centro del cubo
+        1.0,                      // Ancho del cubo
+        1.0,                      // Altura del cubo
+        1.0,                      // Profundidad del cubo
+        suelo.clone(),            // Material de caucho
+    );
+
+    let cuboid19: Cuboid = Cuboid::new(
+        Vec3::new(-1.0, 0.0, 2.0),    // Centro del cubo
+        1.0,                          // Ancho del cubo
+        1.0,                          // Altura del cubo
+        1.0,                          // Profundidad del cubo
+        material_con_textura.clone(), // Material de caucho
+    );
+
+    let cuboid20: Cuboid = Cuboid::new(
+        Vec3::new(-2.0, 0.0, 2.0), // Centro del cubo
+        1.0,                       // Ancho del cubo
+        1.0,                       // Altura del cubo
+        1.0,                       // Profundidad del cubo
+        suelo.clone(),             // Material de caucho
+    );
+
+    let cuboid21: Cuboid = Cuboid::new(
+        Vec3::new(-3.0, 0.0, 0.0), // Centro del cubo
+        1.0,                       // Ancho del cubo
+        1.0,                       // Altura del cubo
+        1.0,                       // Profundidad del cubo
+        texture_bricks.clone(),    // Material de caucho
+    );
+
+    let cuboid22: Cuboid = Cuboid::new(
+        Vec3::new(-3.0, 0.0, 1.0),    // Centro del cubo
+        1.0,                          // Ancho del cubo
+        1.0,                          // Altura del cubo
+        1.0,                          // Profundidad del cubo
+        material_con_textura.clone(), // Material de caucho
+    );
+
+    let cuboid23: Cuboid = Cuboid::new(
+        Vec3::new(-1.0, 0.0, -1.0), // Centro del cubo
+        1.0,                        // Ancho del cubo
+        1.0,                        // Altura del cubo
+        1.0,                        // Profundidad del cubo
+        suelo.clone(),              // Material de caucho
+    );
+
+    let cuboid24: Cuboid = Cuboid::new(
+        Vec3::new(-2.0, 0.0, -1.0), // Centro del cubo
+        1.0,                        // Ancho del cubo
+        1.0,                        // Altura del cubo
+        1.0,                        // Profundidad del cubo
+        texture_bricks.clone(),     // Material de caucho
+    );
+
+    // Lava
+    let cuboid25: Cuboid = Cuboid::new(
+        Vec3::new(0.0, 0.0, -2.0), // Centro del cubo
+        1.0,                       // Ancho del cubo
+        1.0,                       // Altura del cubo
+        1.0,                       // Profundidad del cubo
+        lava.clone(),              // Material de caucho
+    );
+
+    let cuboid26: Cuboid = Cuboid::new(
+        Vec3::new(-1.0, 0.0, -2.0), // Centro del cubo
+        1.0,                        // Ancho del cubo
+        1.0,                        // Altura del cubo
+        1.0,                        // Profundidad del cubo
+        lava.clone(),               // Material de caucho
+    );
+
+    let cuboid27: Cuboid = Cuboid::new(
+        Vec3::new(-2.0, 0.0, -2.0), // Centro del cubo
+        1.0,                        // Ancho del cubo
+        1.0,                        // Altura del cubo
+        1.0,                        // Profundidad del cubo
+        lava.clone(),               // Material de caucho
+    );
+
+    let cuboid28: Cuboid = Cuboid::new(
+        Vec3::new(-3.0, 0.0, -2.0), // Centro del cubo
+        1.0,                        // Ancho del cubo
+        1.0,                        // Altura del cubo
+        1.0,                        // Profundidad del cubo
+        lava.clone(),               // Material de caucho
+    );
+
+    let objects: Vec<Box<dyn RayIntersect>> = vec![
+        // Agua
+        Box::new(cuboid1),
+        Box::new(cuboid2),
+        Box::new(cuboid3),
+        Box::new(cuboid4),
+        // Columnas de ladrillos
+        Box::new(cuboid5),
+        Box::new(cuboid6),
+        Box::new(cuboid7),
+        Box::new(cuboid8),
+        Box::new(cuboid9),
+        Box::new(cuboid10),
+        Box::new(cuboid11),
+        Box::new(cuboid12),
+        Box::new(cuboid13),
+        Box::new(cuboid14),
+        Box::new(cuboid15),
+        Box::new(cuboid16),
+        // Suelo
+        Box::new(cuboid17),
+        Box::new(cuboid18),
+        Box::new(cuboid19),
+        Box::new(cuboid20),
+        Box::new(cuboid21),
+        Box::new(cuboid22),
+        Box::new(cuboid23),
+        Box::new(cuboid24),
+        // Lava
+        Box::new(cuboid25),
+        Box::new(cuboid26),
+        Box::new(cuboid27),
+        Box::new(cuboid28),
+    ];
+
+    Scene::new(objects, lights)
+}
+
+// Campo de `size` x `size` columnas de bloques con alturas onduladas; muchos objetos
+// para medir el costo de las intersecciones
+pub fn voxel_field(size: usize) -> Scene {
+    let grass = Material::new(
+        Color::new(128, 128, 128),
+        100.0,
+        [0.6, 0.3, 0.1, 0.0],
+        1.0,
+        Some(SUELO.clone()),
+        None,
+    )
+    .with_name("suelo");
+    let water = Material::new(
+        Color::new(200, 200, 255),
+        125.0,
+        [0.0, 0.5, 0.7, 0.5],
+        1.33,
+        Some(WATER.clone()),
+        None,
+    )
+    .with_name("agua");
+
+    let half = size as f32 / 2.0;
+    let mut objects: Vec<Box<dyn RayIntersect>> = Vec::new();
+    for x in 0..size {
+        for z in 0..size {
+            let (fx, fz) = (x as f32 - half, z as f32 - half);
+            let height = (1.5 * (fx * 0.5).sin() * (fz * 0.4).cos()).round();
+            // Los valles se llenan de agua
+            let material = if height < 0.0 {
+                water.clone()
+            } else {
+                grass.clone()
+            };
+            objects.push(Box::new(Cuboid::new(
+                Vec3::new(fx, height.max(-0.5), fz),
+                1.0,
+                1.0,
+                1.0,
+                material,
+            )));
+        }
+    }
+
+    let light = Light::new(Vec3::new(5.0, 10.0, 5.0), Color::new(255, 255, 255), 2.0);
+    Scene::new(objects, vec![light])
+}
+
+// Cuadrícula de bloques de vidrio sobre un piso: la mayoría de los rayos se refractan
+pub fn glass_gallery() -> Scene {
+    let glass = Material::new(
+        Color::new(230, 240, 255),
+        125.0,
+        [0.05, 0.3, 0.1, 0.8],
+        1.5,
+        None,
+        None,
+    )
+    .with_name("vidrio");
+    let floor = Material::new(
+        Color::new(255, 255, 255),
+        250.0,
+        [0.9, 0.3, 0.0, 0.0],
+        1.0,
+        Some(BRICKS.clone()),
+        None,
+    )
+    .with_name("bricks");
+
+    let mut objects: Vec<Box<dyn RayIntersect>> = Vec::new();
+    for x in -3..=3 {
+        for z in -3..=3 {
+            objects.push(Box::new(Cuboid::new(
+                Vec3::new(x as f32, -1.0, z as f32),
+                1.0,
+                1.0,
+                1.0,
+                floor.clone(),
+            )));
+        }
+    }
+    for x in -2..=2 {
+        for z in -2..=2 {
+            // Alturas alternadas para que los bloques se vean unos a través de otros
+            let height = if (x + z) % 2 == 0 { 0.8 } else { 0.5 };
+            objects.push(Box::new(Cuboid::new(
+                Vec3::new(x as f32 * 1.2, height / 2.0 - 0.5, z as f32 * 1.2),
+                0.8,
+                height,
+                0.8,
+                glass.clone(),
+            )));
+        }
+    }
+
+    let light = Light::new(Vec3::new(5.0, 10.0, 5.0), Color::new(255, 255, 255), 2.0);
+    Scene::new(objects, vec![light])
+}
@@ -0,0 +1,96 @@
+// Pruebas del archivo de escena: lo que se guarda tiene que volver igual al cargarlo, y
+// un archivo inválido tiene que dar un error en vez de detener el visor
+use crate::color::Color;
+use crate::cuboid::Cuboid;
+use crate::material::Material;
+use crate::scene::Scene;
+use crate::sphere::Sphere;
+use crate::texture::Texture;
+use nalgebra_glm::Vec3;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+// Archivo en el directorio temporal, distinto para cada prueba
+fn temp_file(name: &str) -> PathBuf {
+    env::temp_dir().join(format!("raytracer_{}_{}", std::process::id(), name))
+}
+
+#[test]
+fn save_and_load_keep_materials() {
+    let mut bricks = Material::new(
+        Color::new(200, 120, 40),
+        50.0,
+        [0.9, 0.1, 0.2, 0.0],
+        1.0,
+        Some(Arc::new(Texture::new("assets/ladrillos.png").unwrap())),
+        Some((0.5, 0.25)),
+    )
+    .with_name("ladrillos");
+    bricks.tint = Color::new(255, 128, 64);
+    let mut glow = Material::new(
+        Color::new(40, 200, 40),
+        10.0,
+        [0.8, 0.2, 0.0, 0.3],
+        1.3,
+        None,
+        None,
+    )
+    .with_name("brillo");
+    glow.emission = 0.5;
+    let scene = Scene::new(
+        vec![
+            Box::new(Cuboid::new(Vec3::new(1.0, 2.0, 3.0), 1.0, 2.0, 0.5, bricks)),
+            Box::new(Sphere::new(Vec3::new(-1.0, 0.0, 0.0), 0.75, glow)),
+        ],
+        Vec::new(),
+    );
+
+    let file = temp_file("scene.txt");
+    let file = file.to_str().unwrap();
+    scene.save_objects(file).unwrap();
+    let mut loaded = Scene::new(Vec::new(), Vec::new());
+    let result = loaded.load_objects(file);
+    fs::remove_file(file).unwrap();
+    result.unwrap();
+
+    assert_eq!(loaded.objects.len(), scene.objects.len());
+    for (original, loaded) in scene.objects.iter().zip(&loaded.objects) {
+        assert_eq!(loaded.kind(), original.kind());
+        assert_eq!(loaded.center(), original.center());
+        assert_eq!(loaded.size().x, original.size().x);
+
+        let (a, b) = (original.material(), loaded.material());
+        assert_eq!(b.name, a.name);
+        assert_eq!(b.diffuse, a.diffuse);
+        assert_eq!(b.specular, a.specular);
+        assert_eq!(b.albedo, a.albedo);
+        assert_eq!(b.refractive_index, a.refractive_index);
+        assert_eq!(b.emission, a.emission);
+        assert_eq!(b.tint, a.tint);
+        assert_eq!(b.animation_speed, a.animation_speed);
+        assert_eq!(
+            b.texture.as_ref().map(|texture| &texture.path),
+            a.texture.as_ref().map(|texture| &texture.path)
+        );
+    }
+}
+
+#[test]
+fn texture_that_is_not_an_image_is_an_error() {
+    let file = temp_file("bad_texture.txt");
+    let file = file.to_str().unwrap();
+    fs::write(
+        file,
+        "material roto 255 255 255 10 0.9 0.1 0 0 1 0 255 255 255 Cargo.toml\n\
+         cuboide roto 0 0 0 1 1 1\n",
+    )
+    .unwrap();
+    let result = Scene::new(Vec::new(), Vec::new()).load_objects(file);
+    fs::remove_file(file).unwrap();
+
+    let error = result.unwrap_err();
+    assert_eq!(error.kind(), io::ErrorKind::InvalidData, "{}", error);
+}
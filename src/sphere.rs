@@ -17,9 +17,10 @@ impl Sphere {
         }
     }
 
-    pub fn get_uv(&self, point: &Vec3) -> (f32, f32) {
+    // Coordenadas de textura de `point`, con la esfera centrada en `center`
+    pub fn get_uv(point: &Vec3, center: &Vec3) -> (f32, f32) {
         // Normalizar el vector desde el centro de la esfera al punto de intersección
-        let r = (point - self.center).normalize();
+        let r = (point - center).normalize();
 
         // Calcular θ (theta) y φ (phi)
         let theta = r.z.atan2(r.x); // Arctan(z / x)
@@ -34,11 +35,22 @@ impl Sphere {
 }
 
 impl RayIntersect for Sphere {
-    fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Intersect {
-        let l = self.center - *ray_origin;
+    // El radio es la componente x de `size`, como en `set_size`
+    fn ray_intersect_at(
+        &self,
+        ray_origin: &Vec3,
+        ray_direction: &Vec3,
+        center: &Vec3,
+        size: &Vec3,
+    ) -> Intersect {
+        // La dirección debe estar normalizada
+        let l = center - ray_origin;
         let tca = l.dot(ray_direction);
-        let d2 = l.dot(&l) - tca * tca;
-        let radius2 = self.radius * self.radius;
+        // Distancia al cuadrado del centro al rayo, medida sobre el vector perpendicular:
+        // restar |l|² - tca² pierde toda la precisión cuando el origen está lejos
+        let perpendicular = l - ray_direction * tca;
+        let d2 = perpendicular.dot(&perpendicular);
+        let radius2 = size.x * size.x;
 
         if d2 > radius2 {
             return Intersect::empty();
@@ -58,11 +70,39 @@ impl RayIntersect for Sphere {
         let point = ray_origin + ray_direction * distance;
 
         // Calcular la normal en el punto de impacto
-        let normal = (point - self.center).normalize();
+        let normal = (point - center).normalize();
 
         // Obtener las coordenadas UV
-        let (u, v) = self.get_uv(&point);
+        let (u, v) = Sphere::get_uv(&point, center);
 
         Intersect::new(point, normal, distance, self.material.clone(), u, v)
     }
+
+    fn set_center(&mut self, center: Vec3) {
+        self.center = center;
+    }
+
+    fn set_size(&mut self, size: Vec3) {
+        self.radius = size.x;
+    }
+
+    fn material_mut(&mut self) -> &mut Material {
+        &mut self.material
+    }
+
+    fn center(&self) -> Vec3 {
+        self.center
+    }
+
+    fn size(&self) -> Vec3 {
+        Vec3::repeat(self.radius)
+    }
+
+    fn material(&self) -> &Material {
+        &self.material
+    }
+
+    fn kind(&self) -> &'static str {
+        "esfera"
+    }
 }
@@ -0,0 +1,27 @@
+// Utilidades para los archivos de texto del proyecto (recorridos, marcadores...)
+use std::io;
+
+// Líneas con datos junto a su índice, sin vacías ni comentarios (`#`)
+pub fn data_lines(contents: &str) -> impl Iterator<Item = (usize, &str)> {
+    contents
+        .lines()
+        .enumerate()
+        .map(|(number, line)| (number, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+}
+
+// Convierte una lista de números separados por espacios
+pub fn parse_floats(number: usize, fields: &[&str]) -> io::Result<Vec<f32>> {
+    fields
+        .iter()
+        .map(|value| value.parse::<f32>())
+        .collect::<Result<_, _>>()
+        .map_err(|e| invalid_line(number, &e.to_string()))
+}
+
+pub fn invalid_line(number: usize, reason: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("línea {}: {}", number + 1, reason),
+    )
+}
@@ -1,35 +1,44 @@
 extern crate image;
 
 use crate::color::Color;
-use image::{ImageReader, RgbImage};
+use image::{ImageReader, ImageResult, RgbaImage};
 
 #[derive(Debug, Clone)]
 pub struct Texture {
-    image: RgbImage,
+    image: RgbaImage,
     pub width: u32,
     pub height: u32,
+    pub path: String, // Archivo del que se cargó
 }
 
 impl Texture {
-    pub fn new(file_path: &str) -> Texture {
-        let img = ImageReader::open(file_path)
-            .unwrap()
-            .decode()
-            .unwrap()
-            .to_rgb8();
+    // Falla si el archivo no existe o no es una imagen
+    pub fn new(file_path: &str) -> ImageResult<Texture> {
+        // Conservamos el canal alfa para poder recortar y mezclar texeles transparentes
+        let img = ImageReader::open(file_path)?.decode()?.to_rgba8();
         let width = img.width();
         let height = img.height();
-        Texture {
+        Ok(Texture {
             image: img,
             width,
             height,
-        }
+            path: file_path.to_string(),
+        })
     }
 
     pub fn get_pixel(&self, x: usize, y: usize) -> Color {
+        let pixel = self.texel(x, y);
+        Color::new(pixel[0] as i32, pixel[1] as i32, pixel[2] as i32)
+    }
+
+    // Opacidad del texel en el rango [0, 1]
+    pub fn get_alpha(&self, x: usize, y: usize) -> f32 {
+        self.texel(x, y)[3] as f32 / 255.0
+    }
+
+    fn texel(&self, x: usize, y: usize) -> &image::Rgba<u8> {
         let clamped_x = x.min((self.width - 1) as usize);
         let clamped_y = y.min((self.height - 1) as usize);
-        let pixel = self.image.get_pixel(clamped_x as u32, clamped_y as u32);
-        Color::new(pixel[0] as i32, pixel[1] as i32, pixel[2] as i32)
+        self.image.get_pixel(clamped_x as u32, clamped_y as u32)
     }
 }
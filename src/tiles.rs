@@ -0,0 +1,122 @@
+use crate::color::Color;
+use crate::framebuffer::Framebuffer;
+use crate::renderer::RayStats;
+use rayon::prelude::*;
+use std::time::{Duration, Instant};
+
+// Orden en que se renderizan los mosaicos
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TileOrder {
+    Rows,   // De arriba hacia abajo, de izquierda a derecha
+    Spiral, // Desde el centro hacia afuera, donde suele estar lo importante
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Tile {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+// Cuadro dividido en mosaicos que se puede renderizar por partes y abandonar a medias
+pub struct RenderJob {
+    tiles: Vec<Tile>,
+    next: usize,
+    started: Instant,
+    pub time: f32,       // Tiempo de animación con el que se renderiza todo el cuadro
+    pub stats: RayStats, // Rayos lanzados por los mosaicos ya terminados
+}
+
+impl RenderJob {
+    pub fn new(width: usize, height: usize, tile_size: usize, order: TileOrder, time: f32) -> Self {
+        let tile_size = tile_size.max(1);
+        let mut tiles = Vec::new();
+        for y in (0..height).step_by(tile_size) {
+            for x in (0..width).step_by(tile_size) {
+                tiles.push(Tile {
+                    x,
+                    y,
+                    width: tile_size.min(width - x),
+                    height: tile_size.min(height - y),
+                });
+            }
+        }
+
+        if order == TileOrder::Spiral {
+            // Anillos por distancia al centro, recorridos por ángulo
+            let center_x = width as f32 * 0.5;
+            let center_y = height as f32 * 0.5;
+            let ring_key = |tile: &Tile| {
+                let dx = tile.x as f32 + tile.width as f32 * 0.5 - center_x;
+                let dy = tile.y as f32 + tile.height as f32 * 0.5 - center_y;
+                let ring = (dx.abs().max(dy.abs()) / tile_size as f32).round();
+                (ring, dy.atan2(dx))
+            };
+            tiles.sort_by(|a, b| {
+                let (ring_a, angle_a) = ring_key(a);
+                let (ring_b, angle_b) = ring_key(b);
+                ring_a.total_cmp(&ring_b).then(angle_a.total_cmp(&angle_b))
+            });
+        }
+
+        RenderJob {
+            tiles,
+            next: 0,
+            started: Instant::now(),
+            time,
+            stats: RayStats::default(),
+        }
+    }
+
+    // Tiempo de reloj desde que se creó el trabajo
+    pub fn elapsed(&self) -> Duration {
+        self.started.elapsed()
+    }
+
+    // Fracción de mosaicos terminados, en [0, 1]
+    pub fn progress(&self) -> f32 {
+        if self.tiles.is_empty() {
+            1.0
+        } else {
+            self.next as f32 / self.tiles.len() as f32
+        }
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.next >= self.tiles.len()
+    }
+
+    // Renderiza lotes de mosaicos en paralelo hasta terminar o agotar `budget`.
+    // Devuelve true si el cuadro quedó completo.
+    pub fn render_for(
+        &mut self,
+        framebuffer: &mut Framebuffer,
+        budget: Option<Duration>,
+        shade_tile: impl Fn(&Tile) -> (Vec<Color>, RayStats) + Sync,
+    ) -> bool {
+        let start = Instant::now();
+        let batch_size = rayon::current_num_threads() * 2;
+
+        while !self.is_done() {
+            let end = (self.next + batch_size).min(self.tiles.len());
+            let batch = &self.tiles[self.next..end];
+            let results: Vec<(Vec<Color>, RayStats)> = batch.par_iter().map(&shade_tile).collect();
+
+            for (tile, (colors, stats)) in batch.iter().zip(results) {
+                self.stats += stats;
+                for (row, row_colors) in colors.chunks(tile.width).enumerate() {
+                    let offset = (tile.y + row) * framebuffer.width + tile.x;
+                    framebuffer.buffer[offset..offset + tile.width].copy_from_slice(row_colors);
+                }
+            }
+            self.next = end;
+
+            if budget.is_some_and(|budget| start.elapsed() >= budget) {
+                break;
+            }
+        }
+
+        self.is_done()
+    }
+}